@@ -2,27 +2,76 @@
 //!
 //!
 
+#[cfg(feature = "std")]
+pub mod aco;
+#[cfg(feature = "std")]
+pub mod ase;
+#[cfg(feature = "aseprite")]
+pub mod aseprite;
+pub mod builder;
 pub mod color_segments;
 pub mod colorset_name;
 pub mod common;
+#[cfg(feature = "std")]
+pub mod csv;
+pub mod diff;
+pub mod fidelity;
+#[cfg(feature = "std")]
+pub mod gpl;
+pub mod jasc_pal;
+pub mod riff_pal;
+pub mod sidecar;
+pub mod swatch_svg;
+#[cfg(feature = "std")]
+pub mod web_object;
 pub mod web_utils;
 
-use js_sys::{Boolean, JsString, Number};
 use zerocopy::AsBytes;
 
+use crate::error::ClsError;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
 use bytes::{Bytes, BytesMut};
 use nom;
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
 use serde;
-use web_utils::{cast_js_number, parse_hex_color};
+#[cfg(feature = "std")]
+use std::path::Path;
 
 #[cfg(feature = "web")]
 use crate::wasm::*;
+#[cfg(feature = "web")]
+use js_sys::{Boolean, JsString, Number};
+#[cfg(feature = "web")]
+use web_utils::{cast_js_number, parse_hex_color};
 
 #[cfg_attr(feature = "web", wasm_bindgen)]
-#[derive(Debug, PartialEq, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Colorset {
     name: colorset_name::ColorsetName,
     color_segments: color_segments::ColorSegments,
+    /// Changed since created, parsed or last saved.
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    metadata: sidecar::ColorsetMetadata,
+    /// u32 after the colorset name, whose meaning is unknown.
+    #[serde(skip, default = "default_unknown_number")]
+    unknown_number: u32,
+}
+
+/// The dirty flag and the metadata are not compared.
+impl PartialEq for Colorset {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.color_segments == other.color_segments
+            && self.unknown_number == other.unknown_number
+    }
 }
 
 #[cfg_attr(feature = "web", wasm_bindgen)]
@@ -35,18 +84,817 @@ impl Colorset {
         Colorset {
             name: new_colorset_name,
             color_segments: color_segments::ColorSegments::new(),
+            dirty: false,
+            metadata: sidecar::ColorsetMetadata::default(),
+            unknown_number: DEFAULT_UNKNOWN_NUMBER,
         }
     }
 }
 
+impl<'a> IntoIterator for &'a Colorset {
+    type Item = &'a color_segments::color_segment::ColorSegment;
+    type IntoIter = core::slice::Iter<'a, color_segments::color_segment::ColorSegment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.color_segments.iter()
+    }
+}
+
+/// Same as [`Colorset::new`].
+impl Default for Colorset {
+    fn default() -> Self {
+        Colorset::new()
+    }
+}
+
+/// Same as [`Colorset::from_bytes_strict`].
+impl TryFrom<&[u8]> for Colorset {
+    type Error = ClsError;
+
+    fn try_from(input: &[u8]) -> Result<Self, Self::Error> {
+        Colorset::from_bytes_strict(input)
+    }
+}
+
+/// Same as [`Colorset::from_bytes_strict`].
+impl TryFrom<Vec<u8>> for Colorset {
+    type Error = ClsError;
+
+    fn try_from(input: Vec<u8>) -> Result<Self, Self::Error> {
+        Colorset::from_bytes_strict(&input)
+    }
+}
+
 impl Colorset {
+    /// Create a named Colorset with no color segments, reserving room for `capacity` segments.
+    ///
+    /// # Note
+    /// Unlike [`Colorset::new`], no "Color0" segment is added.
+    pub fn with_capacity(
+        name: &str,
+        capacity: usize,
+    ) -> Result<Colorset, colorset_name::ColorsetNameError> {
+        let mut colorset_name = colorset_name::ColorsetName::new();
+        colorset_name.set_str(name)?;
+
+        Ok(Colorset {
+            name: colorset_name,
+            color_segments: color_segments::ColorSegments::with_capacity(capacity),
+            dirty: false,
+            metadata: sidecar::ColorsetMetadata::default(),
+            unknown_number: DEFAULT_UNKNOWN_NUMBER,
+        })
+    }
+
+    /// Create a named Colorset whose only color segment is `first`.
+    ///
+    /// Unlike [`Colorset::new`], no "Color0" segment is added.
+    pub fn with_first_color(
+        name: &str,
+        first: color_segments::color_segment::ColorSegment,
+    ) -> Result<Colorset, colorset_name::ColorsetNameError> {
+        let mut colorset = Colorset::with_capacity(name, 1)?;
+        colorset.color_segments.push(first);
+
+        Ok(colorset)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn color_segments(&self) -> &color_segments::ColorSegments {
+        &self.color_segments
+    }
+
+    /// # Note
+    /// This marks the colorset dirty, since the color segments may be changed through it.
+    /// Keep at least one color segment, or the cls file cannot be read back.
+    pub fn color_segments_mut(&mut self) -> &mut color_segments::ColorSegments {
+        self.mark_dirty();
+        &mut self.color_segments
+    }
+
+    /// Returns the u32 after the colorset name, which is written back unchanged.
+    ///
+    /// # Note
+    /// Its meaning is unknown. It is 4 in the files known so far, and for new colorsets.
+    pub fn raw_header_value(&self) -> u32 {
+        self.unknown_number
+    }
+
+    /// Returns the number of color segments.
+    pub fn len(&self) -> usize {
+        self.color_segments.len()
+    }
+
+    /// Returns true if there are no color segments, which cannot be serialized to a valid cls file.
+    pub fn is_empty(&self) -> bool {
+        self.color_segments.is_empty()
+    }
+
+    /// Returns the size of the cls bytes, without serializing them.
+    pub fn byte_size(&self) -> u32 {
+        use common::ClsSize;
+        self.size_in_cls()
+    }
+
+    /// Set the serialize mode of all colors.
+    ///
+    /// # Note
+    /// The serialize mode does not affect the cls bytes, so the colorset does not become dirty.
+    pub fn set_serialize_mode(
+        &mut self,
+        serialize_mode: color_segments::color_segment::color::SerializeMode,
+    ) {
+        self.color_segments.iter_mut().for_each(|cs| {
+            cs.get_color_mut_ref()
+                .set_serialize_mode(serialize_mode.clone())
+        });
+    }
+
+    /// Returns true if the colorset was changed since it was created, parsed or last saved.
+    ///
+    /// # Note
+    /// Serializing to cls bytes does not clear it, use [`Colorset::mark_clean`] after saving them.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Clear the dirty flag, e.g. after saving the colorset in another way.
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Push an unnamed opaque color given by hex color string.
+    pub fn push_hex(
+        &mut self,
+        hex_color: &str,
+    ) -> Result<(), color_segments::color_segment::color::ParseHexColorError> {
+        let color =
+            color_segments::color_segment::color::Color::new_with_hex_color(hex_color, false)?;
+        self.color_segments
+            .push(color_segments::color_segment::ColorSegment::new(
+                color, None,
+            ));
+        self.mark_dirty();
+
+        Ok(())
+    }
+
+    /// Create a new Colorset named `name` with the color segments at `indices`, in the given order.
+    pub fn export_selection(&self, indices: &[usize], name: &str) -> Result<Colorset, ClsError> {
+        if indices.is_empty() {
+            return Err(ClsError::EmptySegments);
+        }
+
+        let mut selection = Colorset::with_capacity(name, indices.len())?;
+        for &index in indices {
+            let cs = self
+                .color_segments
+                .get(index)
+                .ok_or(ClsError::IndexOutOfRange {
+                    index,
+                    len: self.color_segments.len(),
+                })?;
+            selection.color_segments.push(cs.clone());
+        }
+
+        Ok(selection)
+    }
+
+    /// Apply gamma correction to all opaque colors.
+    ///
+    /// Each channel is converted as `(channel / 255)^(1 / gamma) * 255`, rounded to the nearest integer.
+    pub fn apply_gamma(&mut self, gamma: f32) -> Result<(), ClsError> {
+        if gamma.is_nan() || gamma <= 0.0 {
+            return Err(ClsError::InvalidArgument(format!(
+                "gamma must be positive, but {}",
+                gamma
+            )));
+        }
+
+        let correct =
+            |channel: u8| ((channel as f32 / 255.0).powf(1.0 / gamma) * 255.0).round() as u8;
+        self.color_segments
+            .iter_mut()
+            .map(|cs| cs.get_color_mut_ref())
+            .filter(|color| !color.get_transparency())
+            .for_each(|color| {
+                let (red, green, blue) = color.get_rgb();
+                color.set_rgb(correct(red), correct(green), correct(blue));
+            });
+        self.mark_dirty();
+
+        Ok(())
+    }
+
+    /// Replace all color segments at once.
+    ///
+    /// `segments` is validated before it is swapped in, so self is left untouched on error.
+    pub fn replace_segments(
+        &mut self,
+        segments: Vec<color_segments::color_segment::ColorSegment>,
+    ) -> Result<(), ClsError> {
+        use color_segments::color_segment::color_name::ColorName;
+
+        if segments.is_empty() {
+            return Err(ClsError::EmptySegments);
+        }
+        for cs in segments.iter() {
+            if let Some(color_name) = cs.get_color_name_ref() {
+                ColorName::validate_str(color_name)?;
+            }
+        }
+
+        let mut color_segments = color_segments::ColorSegments::with_capacity(segments.len());
+        for cs in segments {
+            color_segments.push(cs);
+        }
+        self.color_segments = color_segments;
+        self.mark_dirty();
+
+        Ok(())
+    }
+
+    /// Create a new Colorset of exactly `n` colors by interpolating along the color segments as a gradient.
+    ///
+    /// The first and the last colors are always preserved.
+    /// Colors falling exactly on an original color segment keep it including its name, the others have no name.
+    /// Between a transparent and another color there is no blending, the nearer one is used.
+    /// If `n` is 1, only the first color segment is kept.
+    pub fn interpolate_to(&self, n: usize) -> Result<Colorset, ClsError> {
+        use color_segments::color_segment::{color::Color, ColorSegment};
+
+        if n == 0 || self.color_segments.is_empty() {
+            return Err(ClsError::EmptySegments);
+        }
+
+        let mut interpolated = Colorset::with_capacity(&self.name, n)?;
+        let last = (self.color_segments.len() - 1) as f32;
+        for i in 0..n {
+            let pos = if n == 1 {
+                0.0
+            } else {
+                i as f32 * last / (n - 1) as f32
+            };
+            let lo = (pos.floor() as usize).min(self.color_segments.len() - 1);
+            let frac = pos - lo as f32;
+
+            if frac == 0.0 {
+                interpolated
+                    .color_segments
+                    .push(self.color_segments[lo].clone());
+                continue;
+            }
+
+            let (from, to) = (
+                self.color_segments[lo].get_color_ref(),
+                self.color_segments[lo + 1].get_color_ref(),
+            );
+            let color = if from.get_transparency() || to.get_transparency() {
+                if frac < 0.5 {
+                    from.clone()
+                } else {
+                    to.clone()
+                }
+            } else {
+                let blend = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * frac).round() as u8;
+                let ((fr, fg, fb), (tr, tg, tb)) = (from.get_rgb(), to.get_rgb());
+                Color::new(blend(fr, tr), blend(fg, tg), blend(fb, tb), false)
+            };
+            interpolated
+                .color_segments
+                .push(ColorSegment::new(color, None));
+        }
+
+        Ok(interpolated)
+    }
+
+    /// Append copies of `fill` until the colorset has `n` color segments.
+    ///
+    /// Each appended segment is named by `name_pattern` with `{}` replaced by its index.
+    /// An empty `name_pattern` leaves them unnamed.
+    /// Returns an error if `n` is below the current length, or if a name is too long.
+    pub fn pad_to(
+        &mut self,
+        n: usize,
+        fill: color_segments::color_segment::color::Color,
+        name_pattern: &str,
+    ) -> Result<(), ClsError> {
+        use color_segments::color_segment::{color_name::ColorName, ColorSegment};
+
+        let len = self.color_segments.len();
+        if n < len {
+            return Err(ClsError::InvalidArgument(format!(
+                "cannot pad {} color segments to {}",
+                len, n
+            )));
+        }
+
+        let mut padding = Vec::with_capacity(n - len);
+        for index in len..n {
+            let name = name_pattern.replace("{}", &index.to_string());
+            let color_name = if name.is_empty() {
+                None
+            } else {
+                Some(ColorName::with_str(&name)?)
+            };
+            padding.push(ColorSegment::new(fill.clone(), color_name));
+        }
+
+        for cs in padding {
+            self.color_segments.push(cs);
+        }
+        self.mark_dirty();
+
+        Ok(())
+    }
+
+    /// Append all color segments of `other`, keeping the name of self.
+    pub fn merge(&mut self, mut other: Colorset) {
+        self.color_segments.append(&mut other.color_segments);
+        self.mark_dirty();
+    }
+
+    /// Append the color segments of `other` whose color is not present yet, keeping the name of self.
+    ///
+    /// Colors are compared by [`Color::cls_eq`](color_segments::color_segment::color::Color::cls_eq) ignoring the name,
+    /// also against the ones appended before.
+    /// Returns the number of appended segments.
+    pub fn merge_dedup(&mut self, mut other: Colorset) -> usize {
+        let before = self.color_segments.len();
+        for cs in other.color_segments.drain(..) {
+            if !self
+                .color_segments
+                .iter()
+                .any(|kept_cs| kept_cs.get_color_ref().cls_eq(cs.get_color_ref()))
+            {
+                self.color_segments.push(cs);
+            }
+        }
+
+        let appended = self.color_segments.len() - before;
+        if appended > 0 {
+            self.mark_dirty();
+        }
+        appended
+    }
+
+    /// Sort color segments stably by `key`, then remove consecutive colors within `threshold` by `metric`.
+    ///
+    /// The first segment of each run is kept, so the colorset never becomes empty,
+    /// and the first transparent segment is never removed.
+    /// Returns the number of removed segments.
+    ///
+    /// See [`color_segments::ColorSegments::sort_with_key`] and [`color_segments::ColorSegments::dedup_similar_adjacent`].
+    pub fn sort_and_dedup(
+        &mut self,
+        key: color_segments::SortKey,
+        metric: color_segments::color_segment::color::DistanceMetric,
+        threshold: f32,
+    ) -> usize {
+        self.color_segments
+            .sort_with_key(key, color_segments::SortStability::Stable);
+        let removed = self
+            .color_segments
+            .dedup_similar_adjacent(metric, threshold);
+        self.mark_dirty();
+
+        removed
+    }
+
+    /// Remove transparent color segments except the first one, keeping its name.
+    ///
+    /// Returns the number of removed segments.
+    pub fn dedup_transparent(&mut self) -> usize {
+        let before = self.color_segments.len();
+
+        let mut found_transparent = false;
+        self.color_segments.retain(|cs| {
+            if !cs.get_color_ref().get_transparency() {
+                true
+            } else {
+                !core::mem::replace(&mut found_transparent, true)
+            }
+        });
+
+        let removed = before - self.color_segments.len();
+        if removed > 0 {
+            self.mark_dirty();
+        }
+        removed
+    }
+
+    /// Remove all transparent color segments.
+    ///
+    /// Returns the number of removed segments. If all of them are transparent, nothing is removed and an error is returned.
+    pub fn retain_opaque(&mut self) -> Result<usize, color_segments::ColorSegmentsError> {
+        let removed = self
+            .color_segments
+            .retain_colors(|color| !color.get_transparency())?;
+        if removed > 0 {
+            self.mark_dirty();
+        }
+
+        Ok(removed)
+    }
+
+    /// Truncate all color names to fit within `max_utf16_bytes` when encoded to utf16le.
+    ///
+    /// Names are cut on grapheme cluster boundaries. Shorter names are left untouched.
+    pub fn clamp_names(&mut self, max_utf16_bytes: u16) {
+        let mut truncated = false;
+        self.color_segments
+            .iter_mut()
+            .filter_map(|cs| cs.get_color_name_mut_ref().as_mut())
+            .for_each(|cn| {
+                truncated |= cn.clamp_utf16_bytes(max_utf16_bytes);
+            });
+        if truncated {
+            self.mark_dirty();
+        }
+    }
+
+    /// Iterate colors of color segments.
+    pub fn iter_colors(
+        &self,
+    ) -> impl Iterator<Item = &color_segments::color_segment::color::Color> + '_ {
+        self.color_segments.iter().map(|cs| cs.get_color_ref())
+    }
+
+    /// Iterate hex color strings of color segments.
+    ///
+    /// Transparent colors are empty strings.
+    ///
+    /// # Example
+    /// ```
+    /// use cls_rs::colorset::Colorset;
+    ///
+    /// let mut colorset = Colorset::new();
+    /// colorset.push_hex("#FF8000").unwrap();
+    /// colorset.push_hex("0080ff").unwrap();
+    ///
+    /// let hex_list = colorset.iter_hex(true).collect::<Vec<_>>();
+    /// assert_eq!(hex_list, vec!["", "#FF8000", "#0080FF"]);
+    /// ```
+    pub fn iter_hex(&self, number_sign: bool) -> impl Iterator<Item = String> + '_ {
+        self.color_segments.iter().map(move |cs| {
+            let color = cs.get_color_ref();
+            if color.get_transparency() {
+                String::new()
+            } else {
+                color.get_hex_color(number_sign)
+            }
+        })
+    }
+
+    /// Returns pairs of indices of opaque colors whose contrast ratio is `min_ratio` or more,
+    /// sorted by the ratio in descending order.
+    ///
+    /// See [`Color::contrast_ratio`](color_segments::color_segment::color::Color::contrast_ratio).
+    pub fn max_contrast_pairs(&self, min_ratio: f32) -> Vec<(usize, usize, f32)> {
+        let opaque: Vec<_> = self
+            .color_segments
+            .iter()
+            .enumerate()
+            .filter(|(_, cs)| !cs.get_color_ref().get_transparency())
+            .map(|(idx, cs)| (idx, cs.get_color_ref()))
+            .collect();
+
+        let mut pairs = Vec::new();
+        for (pos, (idx, color)) in opaque.iter().enumerate() {
+            for (other_idx, other_color) in opaque.iter().skip(pos + 1) {
+                let ratio = color.contrast_ratio(other_color);
+                if ratio >= min_ratio {
+                    pairs.push((*idx, *other_idx, ratio));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        pairs
+    }
+
+    /// Group indices of color segments by [`Color::temperature`](color_segments::color_segment::color::Color::temperature).
+    ///
+    /// Returns (warm, cool, neutral).
+    pub fn group_by_temperature(&self) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+        use color_segments::color_segment::color::ColorTemperature::*;
+
+        let mut groups = (Vec::new(), Vec::new(), Vec::new());
+        for (idx, cs) in self.color_segments.iter().enumerate() {
+            match cs.get_color_ref().temperature() {
+                Warm => groups.0.push(idx),
+                Cool => groups.1.push(idx),
+                Neutral => groups.2.push(idx),
+            }
+        }
+
+        groups
+    }
+
+    /// Returns the mean of the RGB of opaque colors, or None if there are none.
+    pub fn average_color(&self) -> Option<color_segments::color_segment::color::Color> {
+        let (count, sums) = self
+            .color_segments
+            .opaque_segments()
+            .map(|cs| cs.get_color_ref().get_rgb())
+            .fold((0u32, [0u32; 3]), |(count, sums), (red, green, blue)| {
+                (
+                    count + 1,
+                    [
+                        sums[0] + red as u32,
+                        sums[1] + green as u32,
+                        sums[2] + blue as u32,
+                    ],
+                )
+            });
+        if count == 0 {
+            return None;
+        }
+
+        let [red, green, blue] = sums.map(|sum| ((sum as f32 / count as f32).round()) as u8);
+        Some(color_segments::color_segment::color::Color::new(
+            red, green, blue, false,
+        ))
+    }
+
+    /// Returns the number of distinct colors, ignoring the names.
+    ///
+    /// Colors are compared by [`Color::cls_eq`](color_segments::color_segment::color::Color::cls_eq),
+    /// so all transparent colors count as one.
+    pub fn unique_color_count(&self) -> usize {
+        use color_segments::color_segment::color::Channel::*;
+
+        self.color_segments
+            .iter()
+            .map(|cs| {
+                [Red, Green, Blue, Alpha].map(|channel| cs.get_color_ref().get_channel(channel))
+            })
+            .collect::<alloc::collections::BTreeSet<_>>()
+            .len()
+    }
+
+    /// Count opaque colors by hue into `bins` slots of equal width, starting at 0 degrees.
+    ///
+    /// # Note
+    /// Achromatic colors have no hue and are not counted.
+    pub fn dominant_hue_buckets(&self, bins: usize) -> Vec<usize> {
+        let mut buckets = vec![0; bins];
+        if bins == 0 {
+            return buckets;
+        }
+
+        for hue in self
+            .color_segments
+            .opaque_segments()
+            .filter_map(|cs| cs.get_color_ref().hue())
+        {
+            let bin = ((hue / 360.0 * bins as f32) as usize).min(bins - 1);
+            buckets[bin] += 1;
+        }
+
+        buckets
+    }
+
+    /// Returns true if both colorsets are written as the same bytes in the cls file.
+    ///
+    /// See [`color_segments::color_segment::ColorSegment::cls_eq`].
+    pub fn cls_eq(&self, other: &Colorset) -> bool {
+        self.name == other.name
+            && self.name.raw_delimiter() == other.name.raw_delimiter()
+            && self.unknown_number == other.unknown_number
+            && self.color_segments.len() == other.color_segments.len()
+            && self
+                .color_segments
+                .iter()
+                .zip(other.color_segments.iter())
+                .all(|(cs, other_cs)| cs.cls_eq(other_cs))
+    }
+
+    /// Returns true if both colorsets have the same color segments in any order.
+    ///
+    /// The colorset names are ignored, and color segments are compared by
+    /// [`color_segments::color_segment::ColorSegment::cls_eq`].
+    pub fn unordered_eq(&self, other: &Colorset) -> bool {
+        if self.color_segments.len() != other.color_segments.len() {
+            return false;
+        }
+
+        let mut matched = vec![false; other.color_segments.len()];
+        self.color_segments.iter().all(|cs| {
+            let found = other
+                .color_segments
+                .iter()
+                .enumerate()
+                .position(|(idx, other_cs)| !matched[idx] && cs.cls_eq(other_cs));
+            match found {
+                Some(idx) => {
+                    matched[idx] = true;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Serialize, re-parse and check the result is [`Colorset::cls_eq`] to self.
+    pub fn verify_roundtrip(&self) -> Result<(), ClsError> {
+        use common::TryFromBytes;
+
+        let colorset_bytes = self.as_bytes();
+        let (rest, parsed) = Colorset::try_from_bytes(&colorset_bytes)?;
+
+        if !rest.is_empty() {
+            return Err(ClsError::RoundtripMismatch(format!(
+                "{} trailing bytes",
+                rest.len()
+            )));
+        }
+        if self.name != parsed.name || self.name.raw_delimiter() != parsed.name.raw_delimiter() {
+            return Err(ClsError::RoundtripMismatch("colorset name".to_string()));
+        }
+        if self.color_segments.len() != parsed.color_segments.len() {
+            return Err(ClsError::RoundtripMismatch(
+                "number of color segments".to_string(),
+            ));
+        }
+        if let Some(idx) = self
+            .color_segments
+            .iter()
+            .zip(parsed.color_segments.iter())
+            .position(|(cs, parsed_cs)| !cs.cls_eq(parsed_cs))
+        {
+            return Err(ClsError::RoundtripMismatch(format!(
+                "color segment {}",
+                idx
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Parse cls bytes, converting the nom error into [`ClsError`].
+    ///
+    /// Like [`TryFromBytes::try_from_bytes`](common::TryFromBytes::try_from_bytes), bytes after the colorset are ignored.
+    pub fn from_bytes(input: &[u8]) -> Result<Colorset, ClsError> {
+        use common::TryFromBytes;
+
+        let (_, colorset) = Colorset::try_from_bytes(input)?;
+        Ok(colorset)
+    }
+
+    /// Parse cls bytes like [`Colorset::from_bytes`], but bytes after the colorset are an error.
+    ///
+    /// Returns [`ClsError::TrailingBytes`] with the number of remaining bytes.
+    pub fn from_bytes_strict(input: &[u8]) -> Result<Colorset, ClsError> {
+        use common::TryFromBytes;
+
+        let options = common::ParseOptions {
+            allow_trailing_bytes: false,
+            ..Default::default()
+        };
+        let (_, colorset) = Colorset::try_from_bytes_with(input, &options)?;
+        Ok(colorset)
+    }
+
+    /// Parse colorsets concatenated in `input`, one item per colorset.
+    ///
+    /// # Note
+    /// Each colorset is parsed from the end of the previous one.
+    /// After a broken one, which is reported as an error item, parsing resumes at the next cls header.
+    /// So bytes that are not a colorset, e.g. leading junk, are reported once and skipped.
+    pub fn iter_from_bytes(input: &[u8]) -> impl Iterator<Item = Result<Colorset, ClsError>> + '_ {
+        use common::TryFromBytes;
+
+        let mut rest = input;
+        core::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            match Colorset::try_from_bytes(rest) {
+                Ok((next, colorset)) => {
+                    rest = next;
+                    Some(Ok(colorset))
+                }
+                Err(err) => {
+                    let resync = rest
+                        .windows(CLS_HEADER.len())
+                        .skip(1)
+                        .position(|window| window == CLS_HEADER)
+                        .map_or(rest.len(), |pos| pos + 1);
+                    rest = &rest[resync..];
+                    Some(Err(err.into()))
+                }
+            }
+        })
+    }
+
+    /// Read and parse the cls file at `path`.
+    ///
+    /// Errors carry the path, and parse errors also carry the offset of the section that failed.
+    #[cfg(feature = "std")]
+    pub fn try_from_path(path: &Path) -> Result<Colorset, ClsError> {
+        let cls_bytes = std::fs::read(path).map_err(|err| ClsError::Io {
+            path: path.to_path_buf(),
+            err,
+        })?;
+        let (_, colorset) =
+            Colorset::try_from_bytes_with_offset(&cls_bytes, &common::ParseOptions::default())
+                .map_err(|(offset, err)| ClsError::File {
+                    path: path.to_path_buf(),
+                    offset,
+                    err: Box::new(err.into()),
+                })?;
+
+        Ok(colorset)
+    }
+
+    /// Read and parse one colorset from `reader`.
+    ///
+    /// Only the bytes of the colorset are read by following its size headers,
+    /// so the reader can be reused for the next colorset.
+    ///
+    /// # Note
+    /// Unlike [`Colorset::from_bytes`], the size headers must be correct.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: std::io::Read>(mut reader: R) -> Result<Colorset, ClsError> {
+        let u32_at = |cls_bytes: &[u8], pos: usize| {
+            u32::from_le_bytes([
+                cls_bytes[pos],
+                cls_bytes[pos + 1],
+                cls_bytes[pos + 2],
+                cls_bytes[pos + 3],
+            ]) as usize
+        };
+        let mut cls_bytes = Vec::new();
+
+        // cls header and the size of the colorset name
+        read_section(&mut reader, &mut cls_bytes, "Colorset", 6 + 4)?;
+        if cls_bytes[..6] != CLS_HEADER {
+            let mut found = [0u8; 6];
+            found.copy_from_slice(&cls_bytes[..6]);
+            return Err(ClsError::InvalidMagicHeader(found));
+        }
+
+        // colorset name, unknown number, number of colors and the size of color segments
+        let name_size = u32_at(&cls_bytes, 6);
+        // overflows on 32bit targets with a broken size header
+        let len = name_size
+            .checked_add(4 + 4 + 4)
+            .ok_or(ClsError::UnexpectedEof {
+                expected: name_size,
+                section: "ColorsetName",
+            })?;
+        read_section(&mut reader, &mut cls_bytes, "ColorsetName", len)?;
+
+        let segments_size = u32_at(&cls_bytes, cls_bytes.len() - 4);
+        read_section(&mut reader, &mut cls_bytes, "ColorSegments", segments_size)?;
+
+        Colorset::from_bytes_strict(&cls_bytes)
+    }
+
+    /// Serialize to cls bytes.
     pub fn as_bytes(&self) -> Bytes {
-        use common::{ClsSize, ExtendBytesMut};
-        let mut colorset_bytes = BytesMut::with_capacity(self.size_in_cls() as usize);
-        self.extend_bytes(&mut colorset_bytes);
+        use common::ClsSection;
+        self.as_cls_bytes()
+    }
+
+    /// Write the cls bytes to `writer`, without building them in memory at once.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        common::ExtendBytesMut::write_to(self, writer)
+    }
+}
 
-        colorset_bytes.freeze()
+/// Append exactly `len` bytes of `section` from `reader` to `buf`.
+#[cfg(feature = "std")]
+fn read_section(
+    reader: &mut impl std::io::Read,
+    buf: &mut Vec<u8>,
+    section: &'static str,
+    len: usize,
+) -> Result<(), ClsError> {
+    use std::io::Read;
+
+    // `take` keeps a broken size header from allocating more than the reader has.
+    let read = reader
+        .take(len as u64)
+        .read_to_end(buf)
+        .map_err(ClsError::Read)?;
+    if read < len {
+        return Err(ClsError::UnexpectedEof {
+            expected: len,
+            section,
+        });
     }
+
+    Ok(())
 }
 
 impl common::ClsSize for Colorset {
@@ -59,7 +907,14 @@ impl common::ClsSize for Colorset {
 }
 
 /// CLS File Header
-const CLS_HEADER: [u8; 6] = [0x53, 0x4C, 0x43, 0x43, 0x00, 0x01];
+pub(crate) const CLS_HEADER: [u8; 6] = [0x53, 0x4C, 0x43, 0x43, 0x00, 0x01];
+
+/// Unknown number written by ClipStudioPaint.
+const DEFAULT_UNKNOWN_NUMBER: u32 = 4;
+
+fn default_unknown_number() -> u32 {
+    DEFAULT_UNKNOWN_NUMBER
+}
 
 // serialize
 impl common::ExtendBytesMut for Colorset {
@@ -71,36 +926,117 @@ impl common::ExtendBytesMut for Colorset {
         self.name.extend_bytes(extended);
 
         // extend unknown number
-        extended.extend_from_slice(4u32.as_bytes());
+        extended.extend_from_slice(self.unknown_number.as_bytes());
 
         // extend color segments
         self.color_segments.extend_bytes(extended);
     }
+
+    #[cfg(feature = "std")]
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
+        writer.write_all(&CLS_HEADER)?;
+        self.name.write_to(writer)?;
+        writer.write_all(self.unknown_number.as_bytes())?;
+        self.color_segments.write_to(writer)
+    }
 }
 
-impl common::TryFromBytes for Colorset {
-    fn try_from_bytes(input: &[u8]) -> nom::IResult<&[u8], Self>
-    where
-        Self: Sized,
-    {
+impl Colorset {
+    /// Same as [`common::TryFromBytes::try_from_bytes_with`],
+    /// but an error comes with the offset of the section that failed from the start of `input`.
+    fn try_from_bytes_with_offset<'a>(
+        input: &'a [u8],
+        options: &common::ParseOptions,
+    ) -> common::OffsetResult<'a, Self> {
+        use common::TryFromBytes;
         use nom::{bytes::complete::take, number::complete::le_u32};
-        // ignore cls header
-        let (input, _) = take(6usize)(input)?;
+        let whole = input;
+        let offset = |rest: &[u8]| whole.len() - rest.len();
+        let at = |rest: &[u8]| {
+            let pos = offset(rest);
+            move |err| (pos, err)
+        };
+
+        // check cls header
+        let (input, header) = take(6usize)(input)
+            .map_err(common::unexpected_eof("Colorset", 6))
+            .map_err(at(input))?;
+        if header != CLS_HEADER {
+            let mut found = [0u8; 6];
+            found.copy_from_slice(header);
+            return Err((0, nom::Err::Failure(ClsError::InvalidMagicHeader(found))));
+        }
         // get colorsetName
-        let (input, colorset_name) = colorset_name::ColorsetName::try_from_bytes(input)?;
-        // ignore unknow val
-        let (input, _) = le_u32(input)?;
+        let (input, colorset_name) =
+            colorset_name::ColorsetName::try_from_bytes_with(input, options).map_err(at(input))?;
+        // get unknown number
+        let (input, unknown_number) = le_u32(input)
+            .map_err(common::unexpected_eof("Colorset", 4))
+            .map_err(at(input))?;
         // get color segments
-        let (input, color_segments) = color_segments::ColorSegments::try_from_bytes(input)?;
+        let (input, color_segments) =
+            color_segments::ColorSegments::try_from_bytes_with_offset(input, options)
+                .map_err(|(segment_offset, err)| (offset(input) + segment_offset, err))?;
+
+        if !options.allow_trailing_bytes && !input.is_empty() {
+            return Err((
+                offset(input),
+                nom::Err::Failure(ClsError::TrailingBytes(input.len())),
+            ));
+        }
 
         let colorset = Colorset {
             name: colorset_name,
             color_segments,
+            dirty: false,
+            metadata: sidecar::ColorsetMetadata::default(),
+            unknown_number,
         };
         Ok((input, colorset))
     }
 }
 
+impl common::TryFromBytes for Colorset {
+    fn try_from_bytes_with<'a>(
+        input: &'a [u8],
+        options: &common::ParseOptions,
+    ) -> nom::IResult<&'a [u8], Self, ClsError>
+    where
+        Self: Sized,
+    {
+        Colorset::try_from_bytes_with_offset(input, options).map_err(|(_, err)| err)
+    }
+}
+
+/// Random
+///
+/// Seeded with ChaCha8, so the same seed always gives the same result.
+#[cfg(feature = "rand")]
+impl Colorset {
+    /// Shuffle color segments.
+    pub fn shuffle(&mut self, seed: u64) {
+        use rand::{seq::SliceRandom, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        self.color_segments.shuffle(&mut rng);
+        self.mark_dirty();
+    }
+
+    /// Create a new Colorset with `k` randomly chosen color segments.
+    ///
+    /// # Note
+    /// If `k` is larger than the number of color segments, all of them are chosen.
+    pub fn random_subset(&self, k: usize, seed: u64) -> Result<Colorset, ClsError> {
+        use rand::SeedableRng;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        let amount = k.min(self.color_segments.len());
+        let indices = rand::seq::index::sample(&mut rng, self.color_segments.len(), amount);
+
+        self.export_selection(&indices.into_vec(), &self.name)
+    }
+}
+
 /// API for wasm
 #[cfg(feature = "web")]
 #[wasm_bindgen]
@@ -128,12 +1064,25 @@ impl Colorset {
 
             None => self.name.set_str("UserColorset"),
         }
-        .map_err(|err| JsValue::from(err.to_string()))
+        .map_err(|err| JsValue::from(err.to_string()))?;
+        self.mark_dirty();
+
+        Ok(())
     }
 
     #[wasm_bindgen(js_name = "getColorsetName")]
     pub fn get_colorset_name(&self) -> JsString {
-        JsString::from(self.name.clone())
+        JsString::from(self.name.as_str())
+    }
+
+    #[wasm_bindgen(js_name = "getColorCount")]
+    pub fn get_color_count(&self) -> Number {
+        Number::from(self.len() as u32)
+    }
+
+    #[wasm_bindgen(js_name = "getByteSize")]
+    pub fn get_byte_size(&self) -> Number {
+        Number::from(self.byte_size())
     }
 
     #[wasm_bindgen(js_name = "setColorName")]
@@ -168,19 +1117,35 @@ impl Colorset {
                 );
             }
         }
+        self.mark_dirty();
 
         Ok(())
     }
 
     #[wasm_bindgen(js_name = "setColorRGB")]
-    pub fn set_color_rgb(&mut self, red: Number, green: Number, blue: Number, idx: Number) {
-        let red: u8 = cast_js_number(red).unwrap();
-        let green: u8 = cast_js_number(green).unwrap();
-        let blue: u8 = cast_js_number(blue).unwrap();
-        let idx: usize = cast_js_number(idx).unwrap();
+    pub fn set_color_rgb(
+        &mut self,
+        red: Number,
+        green: Number,
+        blue: Number,
+        idx: Number,
+    ) -> Result<(), JsValue> {
+        let red: u8 =
+            cast_js_number(red).ok_or_else(|| JsValue::from("Invalid Input Red Value"))?;
+        let green: u8 =
+            cast_js_number(green).ok_or_else(|| JsValue::from("Invalid Input Green Value"))?;
+        let blue: u8 =
+            cast_js_number(blue).ok_or_else(|| JsValue::from("Invalid Input Blue Value"))?;
+        let idx: usize = cast_js_number(idx).ok_or_else(|| JsValue::from("Invalid Index"))?;
 
-        let cs = self.color_segments.get_mut(idx).unwrap();
+        let cs = self
+            .color_segments
+            .get_mut(idx)
+            .ok_or_else(|| JsValue::from(format!("{}th colorsegment does not exist", idx)))?;
         cs.get_color_mut_ref().set_rgb(red, green, blue);
+        self.mark_dirty();
+
+        Ok(())
     }
 
     #[wasm_bindgen(js_name = "setColorHEX")]
@@ -203,17 +1168,31 @@ impl Colorset {
         )?;
 
         cs.get_color_mut_ref().set_rgb(red, green, blue);
+        let color = serde_wasm_bindgen::to_value(cs.get_color_ref());
+        self.mark_dirty();
 
-        serde_wasm_bindgen::to_value(cs.get_color_mut_ref()).map_err(|err| err.into())
+        color.map_err(|err| err.into())
     }
 
     #[wasm_bindgen(js_name = "setColorTransparency")]
-    pub fn set_color_transparency(&mut self, transparency: Boolean, idx: Number) {
-        let transparency = transparency.as_bool().unwrap();
-        let idx: usize = cast_js_number(idx).unwrap();
+    pub fn set_color_transparency(
+        &mut self,
+        transparency: Boolean,
+        idx: Number,
+    ) -> Result<(), JsValue> {
+        let transparency = transparency
+            .as_bool()
+            .ok_or_else(|| JsValue::from("Invalid Input Boolean"))?;
+        let idx: usize = cast_js_number(idx).ok_or_else(|| JsValue::from("Invalid Index"))?;
 
-        let cs = self.color_segments.get_mut(idx).unwrap();
+        let cs = self
+            .color_segments
+            .get_mut(idx)
+            .ok_or_else(|| JsValue::from(format!("{}th colorsegment does not exist", idx)))?;
         cs.get_color_mut_ref().set_transparency(transparency);
+        self.mark_dirty();
+
+        Ok(())
     }
 
     #[wasm_bindgen(js_name = "removeColorSegment")]
@@ -224,6 +1203,51 @@ impl Colorset {
         self.color_segments
             .remove(idx)
             .map_err(|err| JsValue::from(err.to_string()))?;
+        self.mark_dirty();
+
+        Ok(())
+    }
+
+    /// Move the color segment at `from` to `to`, shifting the ones in between. Names are kept.
+    #[wasm_bindgen(js_name = "moveColorSegment")]
+    pub fn move_color_segment(&mut self, from: Number, to: Number) -> Result<(), JsValue> {
+        let from: usize = cast_js_number(from).ok_or_else(|| JsValue::from("Invalid Index"))?;
+        let to: usize = cast_js_number(to).ok_or_else(|| JsValue::from("Invalid Index"))?;
+
+        let len = self.color_segments.len();
+        self.color_segments.move_segment(from, to).map_err(|err| {
+            JsValue::from(format!(
+                "{} (from: {}, to: {}, length: {})",
+                err, from, to, len
+            ))
+        })?;
+        self.mark_dirty();
+
+        Ok(())
+    }
+
+    /// Insert a copy of the color segment at `idx` right after it, see [`color_segments::ColorSegments::duplicate`].
+    #[wasm_bindgen(js_name = "duplicateColorSegment")]
+    pub fn duplicate_color_segment(&mut self, idx: Number) -> Result<(), JsValue> {
+        let idx: usize = cast_js_number(idx).ok_or_else(|| JsValue::from("Invalid Index"))?;
+
+        self.color_segments
+            .duplicate(idx)
+            .map_err(|err| JsValue::from(err.to_string()))?;
+        self.mark_dirty();
+
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = "swapColorSegments")]
+    pub fn swap_color_segments(&mut self, a: Number, b: Number) -> Result<(), JsValue> {
+        let a: usize = cast_js_number(a).ok_or_else(|| JsValue::from("Invalid Index"))?;
+        let b: usize = cast_js_number(b).ok_or_else(|| JsValue::from("Invalid Index"))?;
+
+        self.color_segments
+            .swap(a, b)
+            .map_err(|err| JsValue::from(err.to_string()))?;
+        self.mark_dirty();
 
         Ok(())
     }
@@ -265,6 +1289,7 @@ impl Colorset {
             color_segments::color_segment::ColorSegment::new(new_clr, new_clr_name);
 
         self.color_segments.push(new_clr_segment);
+        self.mark_dirty();
 
         Ok(())
     }
@@ -282,12 +1307,18 @@ impl Colorset {
 
 #[cfg(feature = "web")]
 #[wasm_bindgen(js_name = "withUint8Array")]
-pub fn with_uint8_array(arr: Uint8Array) -> Colorset {
-    use self::common::TryFromBytes;
-
+pub fn with_uint8_array(arr: Uint8Array) -> Result<Colorset, JsValue> {
     let buf = arr.to_vec();
-    let (_, new_cls) = Colorset::try_from_bytes(&buf).unwrap(); // Error 処理入れてどうぞ
-    new_cls
+    let (_, new_cls) = Colorset::try_from_bytes_with_offset(&buf, &common::ParseOptions::default())
+        .map_err(|(offset, err)| {
+            JsValue::from(format!(
+                "Failed to parse cls at byte {} of {}: {}",
+                offset,
+                buf.len(),
+                ClsError::from(err)
+            ))
+        })?;
+    Ok(new_cls)
 }
 
 #[cfg(test)]
@@ -305,4 +1336,919 @@ mod tests {
 
         assert_eq!(de_cs, new_colorset);
     }
+
+    #[test]
+    fn with_capacity_test() {
+        let mut colorset = Colorset::with_capacity("Gray256", 256).unwrap();
+        assert!(colorset.color_segments.is_empty());
+        let reserved = colorset.color_segments.capacity();
+        assert!(reserved >= 256);
+
+        for i in 0..256 {
+            colorset
+                .push_hex(&format!("#{:02X}{:02X}{:02X}", i, i, i))
+                .unwrap();
+        }
+
+        assert_eq!(colorset.color_segments.len(), 256);
+        assert_eq!(colorset.color_segments.capacity(), reserved);
+    }
+
+    #[test]
+    fn truncated_test() {
+        use crate::error::ClsError;
+
+        let cs_b = Colorset::new().as_bytes();
+
+        let err = Colorset::try_from_bytes(&cs_b[..5]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Error(ClsError::UnexpectedEof {
+                expected: 6,
+                section: "Colorset"
+            })
+        ));
+
+        // Every truncation is reported as an error.
+        for len in 0..cs_b.len() {
+            assert!(Colorset::try_from_bytes(&cs_b[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn export_selection_test() {
+        use super::color_segments::color_segment::ColorSegment;
+        use crate::error::ClsError;
+
+        let mut colorset = Colorset::new();
+        for hex in ["#FF0000", "#00FF00", "#0000FF"] {
+            colorset.push_hex(hex).unwrap();
+        }
+
+        let selection = colorset.export_selection(&[2, 0], "Selection").unwrap();
+        assert_eq!(*selection.name, "Selection");
+        assert_eq!(
+            *selection.color_segments,
+            vec![
+                ColorSegment::with_val(0, 255, 0, false, None).unwrap(),
+                ColorSegment::with_val(0, 0, 0, true, Some("Color0")).unwrap(),
+            ]
+        );
+
+        assert!(matches!(
+            colorset.export_selection(&[4], "Selection"),
+            Err(ClsError::IndexOutOfRange { index: 4, len: 4 })
+        ));
+        assert!(matches!(
+            colorset.export_selection(&[], "Selection"),
+            Err(ClsError::EmptySegments)
+        ));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn shuffle_test() {
+        use crate::error::ClsError;
+
+        let setup = || {
+            let mut colorset = Colorset::new();
+            for i in 0..16 {
+                colorset.push_hex(&format!("#{:02X}0000", i * 16)).unwrap();
+            }
+            colorset
+        };
+        let colorset = setup();
+
+        let mut shuffled0 = setup();
+        shuffled0.shuffle(42);
+        let mut shuffled1 = setup();
+        shuffled1.shuffle(42);
+        assert_eq!(shuffled0, shuffled1);
+        assert_ne!(shuffled0, colorset);
+
+        let subset0 = colorset.random_subset(5, 7).unwrap();
+        let subset1 = colorset.random_subset(5, 7).unwrap();
+        assert_eq!(subset0.color_segments.len(), 5);
+        assert_eq!(subset0, subset1);
+
+        assert_eq!(
+            colorset.random_subset(100, 7).unwrap().color_segments.len(),
+            17
+        );
+        assert!(matches!(
+            colorset.random_subset(0, 7),
+            Err(ClsError::EmptySegments)
+        ));
+    }
+
+    #[test]
+    fn apply_gamma_test() {
+        let mut colorset = Colorset::new();
+        colorset.push_hex("#808080").unwrap();
+        colorset.push_hex("#00FF00").unwrap();
+
+        colorset.apply_gamma(2.2).unwrap();
+        assert_eq!(
+            colorset.color_segments[1].get_color_ref().get_rgb(),
+            (186, 186, 186)
+        );
+        assert_eq!(
+            colorset.color_segments[2].get_color_ref().get_rgb(),
+            (0, 255, 0)
+        );
+        // transparent color is untouched
+        assert_eq!(
+            colorset.color_segments[0].get_color_ref().get_rgb(),
+            (0, 0, 0)
+        );
+
+        assert!(colorset.apply_gamma(0.0).is_err());
+        assert!(colorset.apply_gamma(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn clamp_names_test() {
+        use super::color_segments::color_segment::ColorSegment;
+
+        let mut colorset = Colorset::new();
+        let long_name = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        // "👍🏽" is one grapheme of 2 surrogate pairs.
+        let emoji_name = "ABCDEFGHIJKLMN\u{1F44D}\u{1F3FD}";
+        for name in [long_name, emoji_name] {
+            colorset
+                .color_segments
+                .push(ColorSegment::with_val(0, 0, 0, false, Some(name)).unwrap());
+        }
+
+        colorset.clamp_names(32);
+
+        let names = colorset
+            .color_segments
+            .iter()
+            .map(|cs| cs.get_color_name_ref().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Color0", "ABCDEFGHIJKLMNOP", "ABCDEFGHIJKLMN"]);
+
+        let mut clamped = bytes::BytesMut::new();
+        colorset.color_segments[1]
+            .get_color_name_ref()
+            .unwrap()
+            .extend_bytes(&mut clamped);
+        assert_eq!(clamped.len(), 2 + 32);
+    }
+
+    #[test]
+    fn verify_roundtrip_test() {
+        use super::color_segments::color_segment::{color::Color, ColorSegment};
+        use crate::error::ClsError;
+
+        let mut colorset = Colorset::with_capacity("\u{3400}test\u{1f5ff}set", 4).unwrap();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(1, 2, 3, true, Some("Hidden RGB")).unwrap());
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(255, 128, 0, false, Some("\u{1F5FF}")).unwrap());
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(0, 0, 0, false, None).unwrap());
+        assert!(colorset.verify_roundtrip().is_ok());
+
+        // partial alpha is kept
+        colorset
+            .color_segments
+            .push(ColorSegment::new(Color::with_alpha(1, 2, 3, 0x80), None));
+        assert!(colorset.verify_roundtrip().is_ok());
+
+        // empty color segments cannot be parsed
+        let empty = Colorset::with_capacity("Empty", 0).unwrap();
+        assert!(matches!(
+            empty.verify_roundtrip(),
+            Err(ClsError::EmptySegments)
+        ));
+    }
+
+    #[test]
+    fn group_by_temperature_test() {
+        let mut colorset = Colorset::new();
+        for hex in ["#FF0000", "#0000FF", "#808080", "#FFA500"] {
+            colorset.push_hex(hex).unwrap();
+        }
+
+        let (warm, cool, neutral) = colorset.group_by_temperature();
+        assert_eq!(warm, vec![1, 4]);
+        assert_eq!(cool, vec![2]);
+        assert_eq!(neutral, vec![0, 3]);
+    }
+
+    #[test]
+    fn statistics_test() {
+        use super::color_segments::color_segment::{color::Color, ColorSegment};
+
+        let mut colorset = Colorset::new();
+        assert!(colorset.average_color().is_none());
+        assert_eq!(colorset.dominant_hue_buckets(4), vec![0; 4]);
+
+        for hex in ["#FF0000", "#0000FF", "#808080", "#FF0000", "#FFA500"] {
+            colorset.push_hex(hex).unwrap();
+        }
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(1, 2, 3, true, None).unwrap());
+
+        assert_eq!(
+            colorset.average_color(),
+            Some(Color::new(179, 59, 77, false))
+        );
+        // red, blue, gray, orange and transparent
+        assert_eq!(colorset.unique_color_count(), 5);
+        // gray has no hue, and blue is at 240 degrees
+        assert_eq!(colorset.dominant_hue_buckets(4), vec![3, 0, 1, 0]);
+        assert!(colorset.dominant_hue_buckets(0).is_empty());
+    }
+
+    #[test]
+    fn dedup_transparent_test() {
+        use super::color_segments::color_segment::ColorSegment;
+
+        let mut colorset = Colorset::new();
+        colorset.push_hex("#FF0000").unwrap();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(1, 2, 3, true, Some("Clear")).unwrap());
+        colorset.push_hex("#00FF00").unwrap();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(0, 0, 0, true, None).unwrap());
+
+        assert_eq!(colorset.dedup_transparent(), 2);
+        assert_eq!(
+            *colorset.color_segments,
+            vec![
+                ColorSegment::with_val(0, 0, 0, true, Some("Color0")).unwrap(),
+                ColorSegment::with_val(255, 0, 0, false, None).unwrap(),
+                ColorSegment::with_val(0, 255, 0, false, None).unwrap(),
+            ]
+        );
+        assert_eq!(colorset.dedup_transparent(), 0);
+    }
+
+    #[test]
+    fn invalid_magic_header_test() {
+        use crate::error::ClsError;
+
+        let mut cls_bytes = Colorset::new().as_bytes().to_vec();
+        cls_bytes[..6].copy_from_slice(b"\x89PNG\r\n");
+
+        match Colorset::try_from_bytes(&cls_bytes) {
+            Err(nom::Err::Failure(err @ ClsError::InvalidMagicHeader(found))) => {
+                assert_eq!(&found, b"\x89PNG\r\n");
+                assert!(err.to_string().contains("[89, 50, 4E, 47, 0D, 0A]"));
+            }
+            err => panic!("unexpected {:?}", err),
+        }
+    }
+
+    #[test]
+    fn from_bytes_test() {
+        use crate::error::ClsError;
+
+        let colorset = Colorset::new();
+        let mut cls_bytes = colorset.as_bytes().to_vec();
+        assert_eq!(Colorset::from_bytes(&cls_bytes).unwrap(), colorset);
+
+        assert_eq!(Colorset::from_bytes_strict(&cls_bytes).unwrap(), colorset);
+        assert_eq!(Colorset::try_from(cls_bytes.as_slice()).unwrap(), colorset);
+        assert_eq!(Colorset::try_from(cls_bytes.clone()).unwrap(), colorset);
+
+        cls_bytes.extend_from_slice(&[0, 0]);
+        assert_eq!(Colorset::from_bytes(&cls_bytes).unwrap(), colorset);
+        assert!(matches!(
+            Colorset::from_bytes_strict(&cls_bytes),
+            Err(ClsError::TrailingBytes(2))
+        ));
+        assert!(matches!(
+            Colorset::try_from(cls_bytes.clone()),
+            Err(ClsError::TrailingBytes(2))
+        ));
+
+        let pos = 6 + colorset.name.size_in_cls() as usize + 4;
+        cls_bytes[pos] = 2;
+        // the trailing bytes are taken for a broken color segment
+        assert!(matches!(
+            Colorset::from_bytes(&cls_bytes),
+            Err(ClsError::MalformedSegment { offset: 34 })
+        ));
+        cls_bytes.truncate(cls_bytes.len() - 2);
+        assert!(matches!(
+            Colorset::from_bytes(&cls_bytes),
+            Err(ClsError::SegmentCountMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            Colorset::from_bytes(b"\x89PNG\r\n"),
+            Err(ClsError::InvalidMagicHeader(_))
+        ));
+    }
+
+    #[test]
+    fn iter_from_bytes_test() {
+        use super::common::ClsSize;
+        use crate::error::ClsError;
+
+        let first = Colorset::new();
+        let mut second = Colorset::with_capacity("Second", 1).unwrap();
+        second.push_hex("#FF8000").unwrap();
+        // declares no colors
+        let mut broken = first.as_bytes().to_vec();
+        broken[6 + first.name.size_in_cls() as usize + 4] = 0;
+
+        let mut input = b"xx".to_vec();
+        input.extend_from_slice(&first.as_bytes());
+        input.extend_from_slice(&broken);
+        input.extend_from_slice(&second.as_bytes());
+
+        let parsed = Colorset::iter_from_bytes(&input).collect::<Vec<_>>();
+        assert_eq!(parsed.len(), 4);
+        assert!(matches!(parsed[0], Err(ClsError::InvalidMagicHeader(_))));
+        assert_eq!(*parsed[1].as_ref().unwrap(), first);
+        assert!(matches!(parsed[2], Err(ClsError::EmptySegments)));
+        assert_eq!(*parsed[3].as_ref().unwrap(), second);
+
+        // the cls header inside a colorset does not split it
+        let mut header_named = Colorset::with_capacity("SLCC\u{0}\u{1}", 1).unwrap();
+        header_named.push_hex("#534C43").unwrap();
+        let mut input = header_named.as_bytes().to_vec();
+        input.extend_from_slice(&second.as_bytes());
+        let parsed = Colorset::iter_from_bytes(&input)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(parsed, vec![header_named, second]);
+
+        assert_eq!(Colorset::iter_from_bytes(&[]).count(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn from_reader_test() {
+        use crate::error::ClsError;
+
+        let first = Colorset::new();
+        let mut second = Colorset::with_capacity("Second", 1).unwrap();
+        second.push_hex("#FF8000").unwrap();
+
+        let mut input = first.as_bytes().to_vec();
+        input.extend_from_slice(&second.as_bytes());
+        input.extend_from_slice(b"tail");
+
+        let mut reader = input.as_slice();
+        assert_eq!(Colorset::from_reader(&mut reader).unwrap(), first);
+        assert_eq!(Colorset::from_reader(&mut reader).unwrap(), second);
+        assert_eq!(reader, b"tail");
+        assert!(matches!(
+            Colorset::from_reader(&mut reader),
+            Err(ClsError::UnexpectedEof {
+                section: "Colorset",
+                ..
+            })
+        ));
+
+        let cls_bytes = first.as_bytes();
+        assert!(matches!(
+            Colorset::from_reader(&cls_bytes[..cls_bytes.len() - 1]),
+            Err(ClsError::UnexpectedEof {
+                section: "ColorSegments",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Colorset::from_reader(&b"\x89PNG\r\n\0\0\0\0"[..]),
+            Err(ClsError::InvalidMagicHeader(_))
+        ));
+        assert!(matches!(
+            Colorset::from_reader(&b"SLCC\0\x01\xFF\xFF\xFF\xFF"[..]),
+            Err(ClsError::UnexpectedEof {
+                section: "ColorsetName",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn set_serialize_mode_test() {
+        use super::color_segments::color_segment::color::SerializeMode;
+
+        let mut colorset = Colorset::new();
+        colorset.push_hex("#FF8000").unwrap();
+        colorset.mark_clean();
+
+        colorset.set_serialize_mode(SerializeMode::HexWithNumberSign);
+        assert!(
+            colorset
+                .color_segments
+                .iter()
+                .all(|cs| cs.get_color_ref().get_serialize_mode()
+                    == &SerializeMode::HexWithNumberSign)
+        );
+        let json = serde_json::to_string(&colorset.color_segments).unwrap();
+        assert!(json.contains("\"#FF8000\""), "{}", json);
+        assert!(!colorset.is_dirty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_to_test() {
+        let mut colorset = Colorset::new();
+        colorset.push_hex("#FF8000").unwrap();
+        assert!(colorset.is_dirty());
+
+        let mut written = Vec::new();
+        colorset.write_to(&mut written).unwrap();
+        assert!(colorset.is_dirty());
+        assert_eq!(written, colorset.as_bytes().as_ref());
+    }
+
+    #[test]
+    fn iter_test() {
+        let mut colorset = Colorset::new();
+        colorset.push_hex("#FF8000").unwrap();
+
+        let mut names = Vec::new();
+        for cs in &colorset {
+            names.push(cs.get_color_name_ref().map(|name| name.to_string()));
+        }
+        assert_eq!(names, vec![Some("Color0".to_string()), None]);
+
+        let rgbs = colorset
+            .iter_colors()
+            .map(|color| color.get_rgb())
+            .collect::<Vec<_>>();
+        assert_eq!(rgbs, vec![(0, 0, 0), (255, 128, 0)]);
+    }
+
+    #[test]
+    fn accessors_test() {
+        let mut colorset = Colorset::new();
+        assert_eq!(colorset.name(), "NewColorset");
+        assert_eq!(colorset.color_segments().len(), 1);
+        assert!(!colorset.is_dirty());
+
+        colorset.color_segments_mut().push(
+            super::color_segments::color_segment::ColorSegment::with_val(255, 128, 0, false, None)
+                .unwrap(),
+        );
+        assert_eq!(colorset.color_segments().len(), 2);
+        assert!(colorset.is_dirty());
+    }
+
+    #[test]
+    fn len_byte_size_test() {
+        let mut colorset = Colorset::new();
+        assert_eq!(colorset.len(), 1);
+        assert!(!colorset.is_empty());
+        colorset.push_hex("#FF8000").unwrap();
+        assert_eq!(colorset.len(), 2);
+        assert_eq!(colorset.byte_size() as usize, colorset.as_bytes().len());
+
+        assert!(Colorset::with_capacity("Empty", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_number_roundtrip_test() {
+        let colorset = Colorset::new();
+        assert_eq!(colorset.raw_header_value(), 4);
+
+        let mut cls_bytes = colorset.as_bytes().to_vec();
+        let pos = 6 + colorset.name.size_in_cls() as usize;
+        assert_eq!(cls_bytes[pos..pos + 4], [4, 0, 0, 0]);
+        cls_bytes[pos..pos + 4].copy_from_slice(&[7, 0, 0, 0]);
+
+        let (_, parsed) = Colorset::try_from_bytes(&cls_bytes).unwrap();
+        assert_eq!(parsed.raw_header_value(), 7);
+        assert!(!parsed.cls_eq(&colorset));
+        assert_eq!(parsed.as_bytes().as_ref(), cls_bytes.as_slice());
+    }
+
+    #[test]
+    fn raw_delimiter_cls_eq_test() {
+        let colorset = Colorset::new();
+
+        let mut cls_bytes = colorset.as_bytes().to_vec();
+        let pos = 6 + 4 + 2 + colorset.name.encode_sjis().len();
+        assert_eq!(cls_bytes[pos..pos + 4], [0, 0, 0, 0]);
+        cls_bytes[pos..pos + 4].copy_from_slice(&[7, 0, 0, 0]);
+
+        let (_, parsed) = Colorset::try_from_bytes(&cls_bytes).unwrap();
+        assert_eq!(parsed.name, colorset.name);
+        assert!(!parsed.cls_eq(&colorset));
+        assert!(parsed.cls_eq(&parsed.clone()));
+        assert!(parsed.verify_roundtrip().is_ok());
+    }
+
+    #[test]
+    fn default_clone_test() {
+        assert_eq!(Colorset::default(), Colorset::new());
+
+        let mut colorset = Colorset::default();
+        colorset.push_hex("#FF8000").unwrap();
+        colorset.metadata_mut().notes = Some("snapshot".to_string());
+        let snapshot = colorset.clone();
+        assert!(snapshot.is_dirty());
+        assert_eq!(snapshot.metadata(), colorset.metadata());
+
+        colorset.color_segments.clear();
+        colorset.mark_clean();
+        assert_eq!(snapshot.color_segments.len(), 2);
+        assert!(snapshot.is_dirty());
+    }
+
+    #[test]
+    fn merge_test() {
+        use super::common::ClsSize;
+
+        let mut base = Colorset::with_capacity("Base", 2).unwrap();
+        base.push_hex("#FF0000").unwrap();
+        base.push_hex("#00FF00").unwrap();
+        let mut project = Colorset::with_capacity("Project", 3).unwrap();
+        for hex in ["#00FF00", "#0000FF", "#0000FF"] {
+            project.push_hex(hex).unwrap();
+        }
+
+        let mut merged = base.clone();
+        merged.mark_clean();
+        merged.merge(project.clone());
+        assert_eq!(*merged.name, "Base");
+        assert_eq!(merged.color_segments.len(), 5);
+        assert!(merged.is_dirty());
+        let cls_bytes = merged.as_bytes();
+        assert_eq!(cls_bytes.len(), merged.size_in_cls() as usize);
+        let (_, parsed) = Colorset::try_from_bytes(&cls_bytes).unwrap();
+        assert_eq!(parsed, merged);
+
+        let mut merged = base.clone();
+        merged.mark_clean();
+        assert_eq!(merged.merge_dedup(project), 1);
+        assert_eq!(
+            merged.iter_hex(true).collect::<Vec<_>>(),
+            vec!["#FF0000", "#00FF00", "#0000FF"]
+        );
+        assert!(merged.is_dirty());
+
+        merged.mark_clean();
+        assert_eq!(merged.merge_dedup(base), 0);
+        assert!(!merged.is_dirty());
+
+        // the transparent "Color0" of both is the same in the cls file
+        let mut hidden_rgb = Colorset::new();
+        hidden_rgb.color_segments[0]
+            .get_color_mut_ref()
+            .set_serialize_mode_hex();
+        let mut merged = Colorset::new();
+        assert_eq!(merged.merge_dedup(hidden_rgb), 0);
+    }
+
+    #[test]
+    fn pad_to_test() {
+        use super::color_segments::color_segment::color::Color;
+
+        let mut colorset = Colorset::with_capacity("Padded", 3).unwrap();
+        for hex in ["#FF0000", "#00FF00", "#0000FF"] {
+            colorset.push_hex(hex).unwrap();
+        }
+        assert_eq!(colorset.color_segments.len(), 3);
+        colorset.mark_clean();
+
+        colorset
+            .pad_to(8, Color::new(0, 0, 0, true), "Pad {}")
+            .unwrap();
+        assert_eq!(colorset.color_segments.len(), 8);
+        assert!(colorset.is_dirty());
+        for (index, cs) in colorset.color_segments.iter().enumerate().skip(3) {
+            assert!(cs.get_color_ref().get_transparency());
+            assert_eq!(
+                cs.get_color_name_ref().map(|name| name.to_string()),
+                Some(format!("Pad {}", index))
+            );
+        }
+
+        assert!(colorset
+            .pad_to(4, Color::new(0, 0, 0, true), "Pad {}")
+            .is_err());
+        assert!(colorset
+            .pad_to(9, Color::new(0, 0, 0, false), &"t".repeat(65))
+            .is_err());
+        assert_eq!(colorset.color_segments.len(), 8);
+    }
+
+    #[test]
+    fn interpolate_to_test() {
+        let mut colorset = Colorset::with_capacity("Gradient", 3).unwrap();
+        for hex in ["#000000", "#FF0000", "#FFFFFF"] {
+            colorset.push_hex(hex).unwrap();
+        }
+
+        let upsampled = colorset.interpolate_to(5).unwrap();
+        assert_eq!(
+            upsampled.iter_hex(true).collect::<Vec<_>>(),
+            vec!["#000000", "#800000", "#FF0000", "#FF8080", "#FFFFFF"]
+        );
+        assert_eq!(upsampled.color_segments[0], colorset.color_segments[0]);
+        assert_eq!(upsampled.color_segments[4], colorset.color_segments[2]);
+
+        let downsampled = colorset.interpolate_to(2).unwrap();
+        assert_eq!(
+            downsampled.iter_hex(true).collect::<Vec<_>>(),
+            vec!["#000000", "#FFFFFF"]
+        );
+
+        assert!(colorset.interpolate_to(0).is_err());
+    }
+
+    #[test]
+    fn unordered_eq_test() {
+        use super::color_segments::color_segment::ColorSegment;
+
+        let mut colorset = Colorset::with_capacity("Forward", 3).unwrap();
+        let mut reordered = Colorset::with_capacity("Backward", 3).unwrap();
+        let segments = [
+            ColorSegment::with_val(255, 0, 0, false, Some("Red")).unwrap(),
+            ColorSegment::with_val(0, 255, 0, false, None).unwrap(),
+            ColorSegment::with_val(0, 255, 0, false, None).unwrap(),
+        ];
+        for cs in segments.iter() {
+            colorset.color_segments.push(cs.clone());
+        }
+        for cs in segments.iter().rev() {
+            reordered.color_segments.push(cs.clone());
+        }
+        assert!(colorset.unordered_eq(&reordered));
+        assert!(!colorset.cls_eq(&reordered));
+
+        // same length but a different multiset
+        reordered.color_segments[2] = ColorSegment::with_val(0, 255, 0, false, None).unwrap();
+        assert!(!colorset.unordered_eq(&reordered));
+    }
+
+    #[test]
+    fn max_contrast_pairs_test() {
+        let mut colorset = Colorset::new();
+        for hex in ["#000000", "#FFFFFF", "#808080"] {
+            colorset.push_hex(hex).unwrap();
+        }
+
+        let pairs = colorset.max_contrast_pairs(4.0);
+        // The transparent Color0 is skipped, gray-white(about 3.9) is below 4.0.
+        assert_eq!(pairs.len(), 2);
+        assert_eq!((pairs[0].0, pairs[0].1), (1, 2));
+        assert!((pairs[0].2 - 21.0).abs() < 1e-4);
+        assert_eq!((pairs[1].0, pairs[1].1), (1, 3));
+
+        assert!(colorset.max_contrast_pairs(22.0).is_empty());
+    }
+
+    #[test]
+    fn replace_segments_test() {
+        use super::color_segments::color_segment::ColorSegment;
+        use crate::error::ClsError;
+
+        let mut colorset = Colorset::new();
+        colorset.push_hex("#FF0000").unwrap();
+
+        assert!(matches!(
+            colorset.replace_segments(Vec::new()),
+            Err(ClsError::EmptySegments)
+        ));
+        assert_eq!(
+            colorset.iter_hex(true).collect::<Vec<_>>(),
+            vec!["", "#FF0000"]
+        );
+
+        let segments = vec![
+            ColorSegment::with_val(0, 0, 255, false, Some("Blue")).unwrap(),
+            ColorSegment::with_val(0, 255, 0, false, None).unwrap(),
+        ];
+        colorset.replace_segments(segments.clone()).unwrap();
+        assert_eq!(*colorset.color_segments, segments);
+    }
+
+    #[test]
+    fn dirty_test() {
+        use super::common::TryFromBytes;
+
+        let mut colorset = Colorset::new();
+        assert!(!colorset.is_dirty());
+
+        colorset.push_hex("#FF0000").unwrap();
+        assert!(colorset.is_dirty());
+
+        // serializing is not saving
+        let cls_bytes = colorset.as_bytes();
+        assert!(colorset.verify_roundtrip().is_ok());
+        assert!(colorset.is_dirty());
+        colorset.mark_clean();
+        assert!(!colorset.is_dirty());
+        let (_, mut parsed) = Colorset::try_from_bytes(&cls_bytes).unwrap();
+        assert!(!parsed.is_dirty());
+
+        // no-op edits keep it clean
+        assert_eq!(parsed.dedup_transparent(), 0);
+        parsed.clamp_names(128);
+        assert!(!parsed.is_dirty());
+
+        parsed.apply_gamma(2.2).unwrap();
+        assert!(parsed.is_dirty());
+        parsed.mark_clean();
+        assert!(!parsed.is_dirty());
+
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Colorset>();
+    }
+
+    #[test]
+    fn parse_options_test() {
+        use super::common::{ClsSize, ParseOptions, TryFromBytes};
+        use crate::error::ClsError;
+
+        let mut cls_bytes = Colorset::new().as_bytes().to_vec();
+        cls_bytes.extend_from_slice(&[0, 0]);
+
+        let (rest, _) = Colorset::try_from_bytes(&cls_bytes).unwrap();
+        assert_eq!(rest, [0, 0]);
+
+        let options = ParseOptions {
+            allow_trailing_bytes: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Colorset::try_from_bytes_with(&cls_bytes, &options),
+            Err(nom::Err::Failure(ClsError::TrailingBytes(2)))
+        ));
+        cls_bytes.truncate(cls_bytes.len() - 2);
+        assert!(Colorset::try_from_bytes_with(&cls_bytes, &options).is_ok());
+
+        // all options are passed down to the color segments
+        let options = ParseOptions {
+            check_sizes: true,
+            ..Default::default()
+        };
+        // the sjis block of the name is empty without the sjis feature
+        let name_size = Colorset::new().name.size_contents_in_cls() as usize;
+        let segments_size_pos = 6 + 4 + name_size + 4 + 4;
+        cls_bytes[segments_size_pos] += 1;
+        assert!(Colorset::try_from_bytes(&cls_bytes).is_ok());
+        assert!(matches!(
+            Colorset::try_from_bytes_with(&cls_bytes, &options),
+            Err(nom::Err::Failure(ClsError::SizeMismatch {
+                section: "ColorSegments",
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn sort_and_dedup_test() {
+        use super::color_segments::{color_segment::color::DistanceMetric, SortKey};
+
+        let mut colorset = Colorset::new();
+        for hex in [
+            "#FFFFFF", "#000000", "#FEFEFE", "#808080", "#010101", "#FFFFFF", "#7F7F80",
+        ] {
+            colorset.push_hex(hex).unwrap();
+        }
+
+        let removed = colorset.sort_and_dedup(SortKey::Luminance, DistanceMetric::Euclidean, 2.0);
+        assert_eq!(removed, 4);
+        assert_eq!(
+            colorset.iter_hex(true).collect::<Vec<_>>(),
+            vec!["", "#000000", "#7F7F80", "#FEFEFE"]
+        );
+        assert!(colorset.color_segments[0]
+            .get_color_ref()
+            .get_transparency());
+    }
+
+    #[test]
+    fn retain_opaque_test() {
+        use super::color_segments::ColorSegmentsError;
+
+        let mut colorset = Colorset::new();
+        colorset.push_hex("#FF0000").unwrap();
+        colorset.push_hex("#00FF00").unwrap();
+        colorset.color_segments[1]
+            .get_color_mut_ref()
+            .set_transparency(true);
+
+        assert_eq!(colorset.retain_opaque().unwrap(), 2);
+        assert_eq!(colorset.iter_hex(true).collect::<Vec<_>>(), vec!["#00FF00"]);
+        assert_eq!(colorset.retain_opaque().unwrap(), 0);
+
+        let mut transparent = Colorset::new();
+        assert!(matches!(
+            transparent.retain_opaque(),
+            Err(ColorSegmentsError::RetainNothingError)
+        ));
+        assert_eq!(transparent.color_segments.len(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn try_from_path_test() {
+        use crate::error::ClsError;
+        use std::error::Error;
+        use std::path::Path;
+
+        let missing = Path::new("not/exists/colorset.cls");
+        match Colorset::try_from_path(missing) {
+            Err(err @ ClsError::Io { .. }) => {
+                assert!(err.to_string().contains("colorset.cls"));
+                assert!(err.source().unwrap().is::<std::io::Error>());
+            }
+            err => panic!("unexpected {:?}", err),
+        }
+
+        let mut colorset = Colorset::new();
+        colorset.color_segments.push(
+            super::color_segments::color_segment::ColorSegment::with_val(
+                255,
+                0,
+                0,
+                false,
+                Some("Red"),
+            )
+            .unwrap(),
+        );
+        let mut cls_bytes = colorset.as_bytes().to_vec();
+        let path = std::env::temp_dir().join("cls_rs_try_from_path_test.cls");
+        std::fs::write(&path, &cls_bytes).unwrap();
+        assert_eq!(Colorset::try_from_path(&path).unwrap(), colorset);
+
+        // corrupt the size of the last color name
+        let name_size_pos = cls_bytes.len() - 2 - 6;
+        cls_bytes[name_size_pos] = 0xFF;
+        let last_segment_pos = cls_bytes.len() - (4 + 4 + 4 + 2 + 6);
+        std::fs::write(&path, &cls_bytes).unwrap();
+        let err = Colorset::try_from_path(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            err,
+            ClsError::File { offset, .. } if offset == last_segment_pos
+        ));
+        assert!(err.to_string().contains("cls_rs_try_from_path_test.cls"));
+        assert!(matches!(
+            err.source().unwrap().downcast_ref::<ClsError>(),
+            Some(ClsError::UnexpectedEof { .. })
+        ));
+    }
+
+    #[test]
+    fn with_first_color_test() {
+        use super::color_segments::color_segment::ColorSegment;
+
+        let first = ColorSegment::with_val(255, 128, 0, false, Some("Orange")).unwrap();
+        let colorset = Colorset::with_first_color("Warm", first.clone()).unwrap();
+        assert_eq!(*colorset.name, "Warm");
+        assert_eq!(*colorset.color_segments, vec![first.clone()]);
+        assert!(!colorset.is_dirty());
+        assert!(colorset.verify_roundtrip().is_ok());
+
+        assert!(Colorset::with_first_color(&"a".repeat(65), first).is_err());
+    }
+
+    #[test]
+    fn deserialize_test() {
+        use super::color_segments::color_segment::ColorSegment;
+
+        let mut colorset = Colorset::new();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(255, 128, 0, false, Some("Orange")).unwrap());
+        colorset.color_segments[1]
+            .get_color_mut_ref()
+            .set_serialize_mode_hex_with_number_sign();
+
+        let json = serde_json::to_string(&colorset).unwrap();
+        let de_colorset: Colorset = serde_json::from_str(&json).unwrap();
+        assert_eq!(de_colorset, colorset);
+        assert_eq!(serde_json::to_string(&de_colorset).unwrap(), json);
+
+        // hand written
+        let de_colorset: Colorset = serde_json::from_str(
+            r##"{"name":"Handmade","color_segments":[{"color":"#FF8000","color_name":"Orange"}]}"##,
+        )
+        .unwrap();
+        assert_eq!(*de_colorset.name, "Handmade");
+        assert!(de_colorset.color_segments[0].cls_eq(&colorset.color_segments[1]));
+
+        let too_long = format!(
+            r#"{{"name":"{}","color_segments":[{{"color":"","color_name":null}}]}}"#,
+            "t".repeat(65)
+        );
+        assert!(serde_json::from_str::<Colorset>(&too_long).is_err());
+        assert!(
+            serde_json::from_str::<Colorset>(r#"{"name":"Empty","color_segments":[]}"#).is_err()
+        );
+    }
 }
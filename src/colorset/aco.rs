@@ -0,0 +1,275 @@
+//! ACO
+//!
+//! Color swatch format of Photoshop. All numbers are big endian.
+//!
+//! | bytes | contents |
+//! | --- | --- |
+//! | 2 | u16 version(1) |
+//! | 2 | u16 number of colors |
+//! | 10 * n | colors of [u16 color space, u16 * 4 values] |
+//!
+//! It is usually followed by the version 2 section, where each color also has a name.
+//!
+//! | bytes | contents |
+//! | --- | --- |
+//! | 2 | u16 version(2) |
+//! | 2 | u16 number of colors |
+//! | 10 | u16 color space, u16 * 4 values |
+//! | 4 | u32 number of utf16 code units of the name, including the null terminator |
+//! | 2 * n | utf16be name, null terminated |
+//!
+//! # Note
+//! - The version 2 section is used if it exists.
+//! - Only the RGB color space is supported, and its values of 0..=65535 are scaled to 0..=255.
+//! - ACO has no transparency, so colors are opaque.
+
+use super::color_segments::color_segment::{
+    color::Color,
+    color_name::{ColorName, ColorNameError},
+    ColorSegment,
+};
+use super::Colorset;
+use std::{error, fmt, io};
+
+const COLOR_SPACE_RGB: u16 = 0;
+
+/// Returns the name of the ACO color space.
+fn color_space_name(color_space: u16) -> &'static str {
+    match color_space {
+        0 => "RGB",
+        1 => "HSB",
+        2 => "CMYK",
+        7 => "Lab",
+        8 => "Grayscale",
+        9 => "Wide CMYK",
+        _ => "Unknown",
+    }
+}
+
+fn read_u16(input: &mut &[u8]) -> Result<u16, AcoError> {
+    let (bytes, rest) = input
+        .split_first_chunk::<2>()
+        .ok_or(AcoError::UnexpectedEof)?;
+    *input = rest;
+    Ok(u16::from_be_bytes(*bytes))
+}
+
+fn read_u32(input: &mut &[u8]) -> Result<u32, AcoError> {
+    let (bytes, rest) = input
+        .split_first_chunk::<4>()
+        .ok_or(AcoError::UnexpectedEof)?;
+    *input = rest;
+    Ok(u32::from_be_bytes(*bytes))
+}
+
+/// Read a color of [u16 color space, u16 * 4 values].
+fn read_color(input: &mut &[u8]) -> Result<Color, AcoError> {
+    let color_space = read_u16(input)?;
+    let mut values = [0u16; 4];
+    for val in values.iter_mut() {
+        *val = read_u16(input)?;
+    }
+    if color_space != COLOR_SPACE_RGB {
+        return Err(AcoError::UnsupportedColorSpace(color_space));
+    }
+
+    let to_u8 = |val: u16| (val as f32 / 257.0).round() as u8;
+    Ok(Color::new(
+        to_u8(values[0]),
+        to_u8(values[1]),
+        to_u8(values[2]),
+        false,
+    ))
+}
+
+/// Read a section of `version`, returning its color segments.
+fn read_section(input: &mut &[u8], version: u16) -> Result<Vec<ColorSegment>, AcoError> {
+    let count = read_u16(input)?;
+
+    let mut color_segments = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let color = read_color(input)?;
+        let color_name = if version == 2 {
+            let len = read_u32(input)?;
+            // The length is untrusted, so check it against the input before allocating.
+            if len as usize > input.len() / 2 {
+                return Err(AcoError::UnexpectedEof);
+            }
+            let mut name = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                name.push(read_u16(input)?);
+            }
+            if name.last() == Some(&0) {
+                name.pop();
+            }
+            (!name.is_empty())
+                .then(|| ColorName::with_utf16(&name))
+                .transpose()?
+        } else {
+            None
+        };
+        color_segments.push(ColorSegment::new(color, color_name));
+    }
+
+    Ok(color_segments)
+}
+
+impl Colorset {
+    /// Import from ACO bytes.
+    ///
+    /// The colorset is named "NewColorset".
+    pub fn from_aco(mut reader: impl io::Read) -> Result<Colorset, AcoError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        let mut input = buf.as_slice();
+
+        let mut color_segments = match read_u16(&mut input)? {
+            version @ (1 | 2) => read_section(&mut input, version)?,
+            version => return Err(AcoError::UnsupportedVersion(version)),
+        };
+        if !input.is_empty() {
+            match read_u16(&mut input)? {
+                2 => color_segments = read_section(&mut input, 2)?,
+                version => return Err(AcoError::UnsupportedVersion(version)),
+            }
+        }
+        if color_segments.is_empty() {
+            return Err(AcoError::EmptySegments);
+        }
+
+        let mut colorset = Colorset::with_capacity("NewColorset", color_segments.len()).unwrap();
+        for cs in color_segments {
+            colorset.color_segments.push(cs);
+        }
+
+        Ok(colorset)
+    }
+}
+
+#[derive(Debug)]
+pub enum AcoError {
+    Io(io::Error),
+    UnexpectedEof,
+    UnsupportedVersion(u16),
+    UnsupportedColorSpace(u16),
+    ColorName(ColorNameError),
+    EmptySegments,
+}
+
+impl From<io::Error> for AcoError {
+    fn from(err: io::Error) -> Self {
+        AcoError::Io(err)
+    }
+}
+
+impl From<ColorNameError> for AcoError {
+    fn from(err: ColorNameError) -> Self {
+        AcoError::ColorName(err)
+    }
+}
+
+impl fmt::Display for AcoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use AcoError::*;
+        match self {
+            Io(err) => write!(f, "{}", err),
+            UnexpectedEof => write!(f, "Unexpected end of ACO input."),
+            UnsupportedVersion(version) => write!(f, "ACO version {} is unsupported.", version),
+            UnsupportedColorSpace(color_space) => write!(
+                f,
+                "Color space {}({}) is unsupported, only RGB is supported.",
+                color_space_name(*color_space),
+                color_space
+            ),
+            ColorName(err) => write!(f, "{}", err),
+            EmptySegments => write!(f, "Color segments is empty!"),
+        }
+    }
+}
+
+impl error::Error for AcoError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{AcoError, Colorset};
+
+    /// ACO bytes of version 1 and 2 sections with `colors` of (color space, values, name).
+    fn aco_setup(colors: &[(u16, [u16; 4], &str)]) -> Vec<u8> {
+        let mut aco = Vec::new();
+        for version in [1u16, 2] {
+            aco.extend_from_slice(&version.to_be_bytes());
+            aco.extend_from_slice(&(colors.len() as u16).to_be_bytes());
+            for (color_space, values, name) in colors {
+                aco.extend_from_slice(&color_space.to_be_bytes());
+                values
+                    .iter()
+                    .for_each(|val| aco.extend_from_slice(&val.to_be_bytes()));
+                if version == 2 {
+                    let name = name.encode_utf16().chain([0]).collect::<Vec<_>>();
+                    aco.extend_from_slice(&(name.len() as u32).to_be_bytes());
+                    name.iter()
+                        .for_each(|unit| aco.extend_from_slice(&unit.to_be_bytes()));
+                }
+            }
+        }
+        aco
+    }
+
+    #[test]
+    fn from_aco_test() {
+        let aco = aco_setup(&[
+            (0, [65535, 32896, 0, 0], "Orange"),
+            (0, [257, 514, 771, 0], ""),
+        ]);
+
+        let colorset = Colorset::from_aco(aco.as_slice()).unwrap();
+        assert_eq!(
+            colorset.iter_hex(true).collect::<Vec<_>>(),
+            vec!["#FF8000", "#010203"]
+        );
+        let names = colorset
+            .color_segments
+            .iter()
+            .map(|cs| cs.get_color_name_ref().map(|name| name.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![Some("Orange".to_string()), None]);
+
+        // version 1 only
+        let v1_len = 4 + 10 * 2;
+        let colorset = Colorset::from_aco(&aco[..v1_len]).unwrap();
+        assert_eq!(colorset.color_segments.len(), 2);
+        assert!(colorset.color_segments[0].get_color_name_ref().is_none());
+    }
+
+    #[test]
+    fn from_aco_invalid_test() {
+        let cmyk = aco_setup(&[(2, [0, 0, 0, 0], "Black")]);
+        match Colorset::from_aco(cmyk.as_slice()) {
+            Err(err @ AcoError::UnsupportedColorSpace(2)) => {
+                assert!(err.to_string().contains("CMYK"))
+            }
+            err => panic!("unexpected {:?}", err),
+        }
+
+        let aco = aco_setup(&[(0, [0, 0, 0, 0], "Black")]);
+        assert!(matches!(
+            Colorset::from_aco(&aco[..aco.len() - 2]),
+            Err(AcoError::UnexpectedEof)
+        ));
+        let mut huge_name = aco_setup(&[(0, [0, 0, 0, 0], "")]);
+        let len_pos = huge_name.len() - 2 - 4;
+        huge_name[len_pos..len_pos + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            Colorset::from_aco(huge_name.as_slice()),
+            Err(AcoError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            Colorset::from_aco([0u8, 3, 0, 0].as_slice()),
+            Err(AcoError::UnsupportedVersion(3))
+        ));
+        assert!(matches!(
+            Colorset::from_aco([0u8, 1, 0, 0].as_slice()),
+            Err(AcoError::EmptySegments)
+        ));
+    }
+}
@@ -0,0 +1,108 @@
+//! ASE
+//!
+//! Adobe Swatch Exchange, the swatch format of Photoshop and Illustrator. All numbers are big endian.
+//!
+//! | bytes | contents |
+//! | --- | --- |
+//! | 4 | "ASEF" |
+//! | 2 + 2 | u16 version(1.0) |
+//! | 4 | u32 number of blocks |
+//! | 2 | u16 block type(0x0001 color entry) |
+//! | 4 | u32 size of the rest of the block |
+//! | 2 | u16 number of utf16 code units of the name, including the null terminator |
+//! | 2 * n | utf16be name, null terminated |
+//! | 4 | "RGB " |
+//! | 4 * 3 | f32 red, green, blue in 0.0..=1.0 |
+//! | 2 | u16 color type(2 normal) |
+//!
+//! # Note
+//! ASE has no transparency, so transparent colors are skipped on export.
+//! Colors without a name are exported with an empty name.
+
+use super::Colorset;
+use std::io;
+
+const ASE_SIGNATURE: &[u8; 4] = b"ASEF";
+const ASE_VERSION: (u16, u16) = (1, 0);
+const COLOR_ENTRY: u16 = 0x0001;
+const COLOR_TYPE_NORMAL: u16 = 2;
+
+impl Colorset {
+    /// Export to ASE bytes.
+    pub fn to_ase(&self, mut writer: impl io::Write) -> io::Result<()> {
+        let opaque_segments = self
+            .color_segments
+            .iter()
+            .filter(|cs| !cs.get_color_ref().get_transparency())
+            .collect::<Vec<_>>();
+
+        writer.write_all(ASE_SIGNATURE)?;
+        writer.write_all(&ASE_VERSION.0.to_be_bytes())?;
+        writer.write_all(&ASE_VERSION.1.to_be_bytes())?;
+        writer.write_all(&(opaque_segments.len() as u32).to_be_bytes())?;
+
+        for cs in opaque_segments {
+            let mut name = cs
+                .get_color_name_ref()
+                .map(|name| name.encode_utf16().collect::<Vec<_>>())
+                .unwrap_or_default();
+            name.push(0);
+            let (red, green, blue) = cs.get_color_ref().to_rgb_f32();
+
+            let mut block = Vec::with_capacity(2 + 2 * name.len() + 4 + 12 + 2);
+            block.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            name.iter()
+                .for_each(|unit| block.extend_from_slice(&unit.to_be_bytes()));
+            block.extend_from_slice(b"RGB ");
+            for channel in [red, green, blue] {
+                block.extend_from_slice(&channel.to_be_bytes());
+            }
+            block.extend_from_slice(&COLOR_TYPE_NORMAL.to_be_bytes());
+
+            writer.write_all(&COLOR_ENTRY.to_be_bytes())?;
+            writer.write_all(&(block.len() as u32).to_be_bytes())?;
+            writer.write_all(&block)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::ColorSegment;
+    use super::Colorset;
+
+    #[test]
+    fn to_ase_test() {
+        let mut colorset = Colorset::new();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(255, 0, 0, false, Some("Red")).unwrap());
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(0, 0, 255, false, None).unwrap());
+
+        let mut ase = Vec::new();
+        colorset.to_ase(&mut ase).unwrap();
+
+        // header, the transparent "Color0" is skipped
+        assert_eq!(ase[..12], *b"ASEF\x00\x01\x00\x00\x00\x00\x00\x02");
+
+        // "Red"
+        let mut red = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x1C, 0x00, 0x04];
+        red.extend_from_slice(&[0x00, b'R', 0x00, b'e', 0x00, b'd', 0x00, 0x00]);
+        red.extend_from_slice(b"RGB ");
+        for channel in [1.0f32, 0.0, 0.0] {
+            red.extend_from_slice(&channel.to_be_bytes());
+        }
+        red.extend_from_slice(&[0x00, 0x02]);
+        assert_eq!(ase[12..12 + red.len()], red);
+
+        // unnamed blue
+        let blue = &ase[12 + red.len()..];
+        assert_eq!(blue.len(), 6 + 2 + 2 + 4 + 12 + 2);
+        assert_eq!(blue[6..10], [0x00, 0x01, 0x00, 0x00]);
+        assert_eq!(blue[22..26], 1.0f32.to_be_bytes());
+    }
+}
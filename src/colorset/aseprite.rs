@@ -0,0 +1,271 @@
+//! Aseprite
+//!
+//! Palette of the native file format of Aseprite(`.aseprite`, `.ase`).
+//!
+//! | bytes | contents |
+//! | --- | --- |
+//! | 128 | file header, u16 magic number(0xA5E0) at offset 4 |
+//! | 16 | frame header, u16 magic number(0xF1FA) at offset 4 |
+//! | ... | chunks of [u32 size, u16 type, data] |
+//!
+//! Only the chunks of the first frame are read, where the palette is stored.
+//!     - Palette chunk(0x2019) : entries of [u16 flags, red, green, blue, alpha, (u16 size, utf8 name)]
+//!     - Old palette chunk(0x0004) : packets of [u8 entries to skip, u8 number of colors, (red, green, blue) * n]
+//!
+//! # Note
+//! - The palette chunk is used if both exist.
+//! - Alpha is kept as is, and alpha 0 is transparent.
+//! - The old palette chunk has no alpha, so its colors are opaque.
+
+use super::color_segments::color_segment::ColorSegment;
+use super::{common, Colorset};
+use crate::error::ClsError;
+use alloc::{format, string::String, vec, vec::Vec};
+use nom;
+
+const FILE_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+const OLD_PALETTE_CHUNK: u16 = 0x0004;
+const PALETTE_CHUNK: u16 = 0x2019;
+
+/// A palette entry of [red, green, blue, alpha] and name.
+type Entry = ([u8; 4], Option<String>);
+
+/// Size of an unnamed entry of the palette chunk, u16 flags and rgba.
+const MIN_ENTRY_SIZE: usize = 2 + 4;
+
+impl Colorset {
+    /// Import the palette of an Aseprite file.
+    ///
+    /// The colorset is named "NewColorset".
+    pub fn from_aseprite(input: &[u8]) -> Result<Colorset, ClsError> {
+        use common::unexpected_eof;
+        use nom::bytes::complete::take;
+        use nom::number::complete::{le_u16, le_u32};
+
+        let invalid = |msg: &str| ClsError::InvalidFormat(format!("Aseprite {}", msg));
+
+        // file header
+        let (rest, header) = take(128usize)(input).map_err(unexpected_eof("Aseprite", 128))?;
+        let (_, magic) = le_u16(&header[4..])?;
+        if magic != FILE_MAGIC {
+            return Err(invalid("magic number is wrong"));
+        }
+
+        // first frame header, ignoring the frame size
+        let (rest, _) = le_u32(rest).map_err(unexpected_eof("Aseprite", 4))?;
+        let (rest, magic) = le_u16(rest).map_err(unexpected_eof("Aseprite", 2))?;
+        if magic != FRAME_MAGIC {
+            return Err(invalid("frame magic number is wrong"));
+        }
+        let (rest, old_num_chunks) = le_u16(rest).map_err(unexpected_eof("Aseprite", 2))?;
+        let (rest, _) = take(4usize)(rest).map_err(unexpected_eof("Aseprite", 4))?;
+        let (mut rest, num_chunks) = le_u32(rest).map_err(unexpected_eof("Aseprite", 4))?;
+        let num_chunks = if num_chunks == 0 {
+            old_num_chunks as u32
+        } else {
+            num_chunks
+        };
+
+        // chunks
+        let mut palette = None;
+        let mut old_palette = None;
+        for _ in 0..num_chunks {
+            let (chunk_rest, chunk_size) = le_u32(rest).map_err(unexpected_eof("Aseprite", 4))?;
+            let (chunk_rest, chunk_type) =
+                le_u16(chunk_rest).map_err(unexpected_eof("Aseprite", 2))?;
+            let data_size = (chunk_size as usize)
+                .checked_sub(6)
+                .ok_or_else(|| invalid("chunk size is too small"))?;
+            let (chunk_rest, data) =
+                take(data_size)(chunk_rest).map_err(unexpected_eof("Aseprite", data_size))?;
+            rest = chunk_rest;
+
+            match chunk_type {
+                PALETTE_CHUNK => palette = Some(parse_palette_chunk(data)?),
+                OLD_PALETTE_CHUNK => old_palette = Some(parse_old_palette_chunk(data)?),
+                _ => {}
+            }
+        }
+
+        let entries: Vec<Entry> = palette
+            .or(old_palette)
+            .ok_or_else(|| invalid("palette is not found"))?
+            .into_iter()
+            .flatten()
+            .collect();
+        if entries.is_empty() {
+            return Err(ClsError::EmptySegments);
+        }
+
+        let mut colorset = Colorset::with_capacity("NewColorset", entries.len())?;
+        for ([red, green, blue, alpha], name) in entries {
+            let mut cs = ColorSegment::with_val(red, green, blue, alpha == 0, name.as_deref())?;
+            cs.get_color_mut_ref().set_alpha(alpha);
+            colorset.color_segments.push(cs);
+        }
+
+        Ok(colorset)
+    }
+}
+
+/// Parse the palette chunk(0x2019) into entries by index.
+fn parse_palette_chunk(data: &[u8]) -> Result<Vec<Option<Entry>>, ClsError> {
+    use common::unexpected_eof;
+    use nom::bytes::complete::take;
+    use nom::number::complete::{le_u16, le_u32};
+
+    let (data, palette_size) = le_u32(data).map_err(unexpected_eof("Aseprite palette", 4))?;
+    let (data, first) = le_u32(data).map_err(unexpected_eof("Aseprite palette", 4))?;
+    let (data, last) = le_u32(data).map_err(unexpected_eof("Aseprite palette", 4))?;
+    let (mut data, _) = take(8usize)(data).map_err(unexpected_eof("Aseprite palette", 8))?;
+    if first > last || last >= palette_size {
+        return Err(ClsError::InvalidFormat(format!(
+            "Aseprite palette range {}..={} is out of {} entries",
+            first, last, palette_size
+        )));
+    }
+    // The sizes are untrusted, so check them against the bytes before allocating.
+    if palette_size as usize > data.len() / MIN_ENTRY_SIZE {
+        return Err(ClsError::InvalidFormat(format!(
+            "Aseprite palette of {} entries in {} bytes",
+            palette_size,
+            data.len()
+        )));
+    }
+
+    let mut entries = vec![None; last as usize + 1];
+    for entry in entries[first as usize..=last as usize].iter_mut() {
+        let (rest, flags) = le_u16(data).map_err(unexpected_eof("Aseprite palette", 2))?;
+        let (rest, rgba) = take(4usize)(rest).map_err(unexpected_eof("Aseprite palette", 4))?;
+        let (rest, name) = if flags & 1 == 1 {
+            let (rest, name_size) = le_u16(rest).map_err(unexpected_eof("Aseprite palette", 2))?;
+            let (rest, name) = take(name_size as usize)(rest)
+                .map_err(unexpected_eof("Aseprite palette", name_size as usize))?;
+            (rest, Some(String::from_utf8(name.to_vec())?))
+        } else {
+            (rest, None)
+        };
+        *entry = Some(([rgba[0], rgba[1], rgba[2], rgba[3]], name));
+        data = rest;
+    }
+
+    Ok(entries)
+}
+
+/// Parse the old palette chunk(0x0004) into entries by index.
+fn parse_old_palette_chunk(data: &[u8]) -> Result<Vec<Option<Entry>>, ClsError> {
+    use common::unexpected_eof;
+    use nom::bytes::complete::take;
+    use nom::number::complete::{le_u16, le_u8};
+
+    let (mut data, num_packets) = le_u16(data).map_err(unexpected_eof("Aseprite palette", 2))?;
+
+    let mut entries = Vec::new();
+    for _ in 0..num_packets {
+        let (rest, skip) = le_u8(data).map_err(unexpected_eof("Aseprite palette", 1))?;
+        let (rest, num_colors) = le_u8(rest).map_err(unexpected_eof("Aseprite palette", 1))?;
+        let num_colors = if num_colors == 0 {
+            256
+        } else {
+            num_colors as usize
+        };
+        let (rest, colors) = take(num_colors * 3)(rest)
+            .map_err(unexpected_eof("Aseprite palette", num_colors * 3))?;
+
+        entries.resize(entries.len() + skip as usize, None);
+        entries.extend(
+            colors
+                .chunks_exact(3)
+                .map(|rgb| Some(([rgb[0], rgb[1], rgb[2], 0xFF], None))),
+        );
+        data = rest;
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::{color::Color, ColorSegment};
+    use super::Colorset;
+    use crate::error::ClsError;
+
+    /// A file with one frame holding `chunks` of (type, data).
+    fn aseprite_setup(chunks: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        for (chunk_type, data) in chunks {
+            frame.extend_from_slice(&(6 + data.len() as u32).to_le_bytes());
+            frame.extend_from_slice(&chunk_type.to_le_bytes());
+            frame.extend_from_slice(data);
+        }
+
+        let mut header = vec![0u8; 128];
+        header[4..6].copy_from_slice(&0xA5E0u16.to_le_bytes());
+
+        let mut file = header;
+        file.extend_from_slice(&(16 + frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&0xF1FAu16.to_le_bytes());
+        file.extend_from_slice(&(chunks.len() as u16).to_le_bytes());
+        file.extend_from_slice(&[100, 0, 0, 0]);
+        file.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+        file.extend_from_slice(&frame);
+        file
+    }
+
+    #[test]
+    fn palette_chunk_test() {
+        let mut palette = Vec::new();
+        for val in [3u32, 0, 2] {
+            palette.extend_from_slice(&val.to_le_bytes());
+        }
+        palette.extend_from_slice(&[0; 8]);
+        palette.extend_from_slice(&[1, 0, 255, 128, 0, 255, 6, 0]);
+        palette.extend_from_slice(b"Orange");
+        palette.extend_from_slice(&[0, 0, 1, 2, 3, 0]);
+        palette.extend_from_slice(&[0, 0, 10, 20, 30, 128]);
+
+        let old_palette = vec![1, 0, 0, 1, 9, 9, 9];
+        let file = aseprite_setup(&[(0x0004, old_palette.clone()), (0x2019, palette)]);
+
+        let colorset = Colorset::from_aseprite(&file).unwrap();
+        assert_eq!(
+            *colorset.color_segments,
+            vec![
+                ColorSegment::with_val(255, 128, 0, false, Some("Orange")).unwrap(),
+                ColorSegment::with_val(1, 2, 3, true, None).unwrap(),
+                ColorSegment::new(Color::with_alpha(10, 20, 30, 128), None),
+            ]
+        );
+
+        // old palette chunk only
+        let colorset = Colorset::from_aseprite(&aseprite_setup(&[(0x0004, old_palette)])).unwrap();
+        assert_eq!(colorset.iter_hex(true).collect::<Vec<_>>(), vec!["#090909"]);
+    }
+
+    #[test]
+    fn aseprite_invalid_test() {
+        let file = aseprite_setup(&[]);
+        assert!(matches!(
+            Colorset::from_aseprite(&file),
+            Err(ClsError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            Colorset::from_aseprite(&file[..100]),
+            Err(ClsError::UnexpectedEof { .. })
+        ));
+
+        // palette size far beyond the chunk
+        let mut palette = Vec::new();
+        for val in [u32::MAX, 0, u32::MAX - 1] {
+            palette.extend_from_slice(&val.to_le_bytes());
+        }
+        palette.extend_from_slice(&[0; 8]);
+        palette.extend_from_slice(&[0, 0, 1, 2, 3, 255]);
+        let file = aseprite_setup(&[(0x2019, palette)]);
+        assert!(matches!(
+            Colorset::from_aseprite(&file),
+            Err(ClsError::InvalidFormat(_))
+        ));
+    }
+}
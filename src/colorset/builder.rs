@@ -0,0 +1,191 @@
+//! Builder
+//!
+//! Fluent construction of [`Colorset`].
+//!
+//! # Example
+//! ```
+//! use cls_rs::colorset::builder::ColorsetBuilder;
+//!
+//! let colorset = ColorsetBuilder::new()
+//!     .name("Sunset")
+//!     .add_hex("#FF8000")
+//!     .add_rgb(255, 0, 0)
+//!     .add_transparent()
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(colorset.iter_hex(true).collect::<Vec<_>>(), vec!["#FF8000", "#FF0000", ""]);
+//! ```
+
+use super::color_segments::color_segment::{
+    color::{Color, ParseHexColorError},
+    color_name::{ColorName, ColorNameError},
+    ColorSegment,
+};
+use super::colorset_name::ColorsetNameError;
+use super::Colorset;
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::{error, fmt};
+
+/// ColorsetBuilder
+///
+/// Unlike [`Colorset::new`], it starts without color segments.
+/// The first error of the methods is returned by [`ColorsetBuilder::build`].
+#[derive(Debug, Default)]
+pub struct ColorsetBuilder {
+    name: Option<String>,
+    color_segments: Vec<ColorSegment>,
+    err: Option<BuildError>,
+}
+
+impl ColorsetBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the colorset name. The default is "NewColorset".
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Add an unnamed opaque color.
+    pub fn add_rgb(self, red: u8, green: u8, blue: u8) -> Self {
+        self.add(Color::new(red, green, blue, false), None)
+    }
+
+    /// Add an unnamed opaque color given by hex color string.
+    pub fn add_hex(mut self, hex_color: &str) -> Self {
+        match Color::new_with_hex_color(hex_color, false) {
+            Ok(color) => self.add(color, None),
+            Err(err) => {
+                self.err.get_or_insert(BuildError::InvalidHex(err));
+                self
+            }
+        }
+    }
+
+    /// Add an unnamed transparent color.
+    pub fn add_transparent(self) -> Self {
+        self.add(Color::new(0, 0, 0, true), None)
+    }
+
+    pub fn add_named(mut self, name: &str, color: Color) -> Self {
+        match ColorName::with_str(name) {
+            Ok(color_name) => self.add(color, Some(color_name)),
+            Err(err) => {
+                self.err.get_or_insert(BuildError::ColorName(err));
+                self
+            }
+        }
+    }
+
+    fn add(mut self, color: Color, color_name: Option<ColorName>) -> Self {
+        self.color_segments
+            .push(ColorSegment::new(color, color_name));
+        self
+    }
+
+    /// Build the Colorset. Like the cls parser, at least one color segment is required.
+    pub fn build(self) -> Result<Colorset, BuildError> {
+        if let Some(err) = self.err {
+            return Err(err);
+        }
+        if self.color_segments.is_empty() {
+            return Err(BuildError::EmptySegments);
+        }
+
+        let mut colorset = Colorset::with_capacity(
+            self.name.as_deref().unwrap_or("NewColorset"),
+            self.color_segments.len(),
+        )?;
+        for cs in self.color_segments {
+            colorset.color_segments.push(cs);
+        }
+
+        Ok(colorset)
+    }
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    ColorsetName(ColorsetNameError),
+    ColorName(ColorNameError),
+    InvalidHex(ParseHexColorError),
+    EmptySegments,
+}
+
+impl From<ColorsetNameError> for BuildError {
+    fn from(err: ColorsetNameError) -> Self {
+        BuildError::ColorsetName(err)
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use BuildError::*;
+        match self {
+            ColorsetName(err) => write!(f, "{}", err),
+            ColorName(err) => write!(f, "{}", err),
+            InvalidHex(err) => write!(f, "{}", err),
+            EmptySegments => write!(f, "Color segments is empty!"),
+        }
+    }
+}
+
+impl error::Error for BuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::{color::Color, ColorSegment};
+    use super::{BuildError, ColorsetBuilder};
+
+    #[test]
+    fn build_test() {
+        let colorset = ColorsetBuilder::new()
+            .name("Built")
+            .add_rgb(1, 2, 3)
+            .add_named("Orange", Color::new(255, 128, 0, false))
+            .add_transparent()
+            .build()
+            .unwrap();
+
+        assert_eq!(*colorset.name, "Built");
+        assert_eq!(
+            *colorset.color_segments,
+            vec![
+                ColorSegment::with_val(1, 2, 3, false, None).unwrap(),
+                ColorSegment::with_val(255, 128, 0, false, Some("Orange")).unwrap(),
+                ColorSegment::with_val(0, 0, 0, true, None).unwrap(),
+            ]
+        );
+        assert!(!colorset.is_dirty());
+    }
+
+    #[test]
+    fn build_invalid_test() {
+        assert!(matches!(
+            ColorsetBuilder::new().name("Empty").build(),
+            Err(BuildError::EmptySegments)
+        ));
+        assert!(matches!(
+            ColorsetBuilder::new()
+                .add_hex("#GG0000")
+                .add_rgb(0, 0, 0)
+                .build(),
+            Err(BuildError::InvalidHex(_))
+        ));
+        assert!(matches!(
+            ColorsetBuilder::new()
+                .add_named(&"t".repeat(65), Color::new(0, 0, 0, false))
+                .build(),
+            Err(BuildError::ColorName(_))
+        ));
+        assert!(matches!(
+            ColorsetBuilder::new()
+                .name(&"t".repeat(65))
+                .add_rgb(0, 0, 0)
+                .build(),
+            Err(BuildError::ColorsetName(_))
+        ));
+    }
+}
@@ -5,16 +5,38 @@ pub mod color_segment;
 use color_segment::{color, color_name};
 
 use crate::colorset::common;
+use crate::error::ClsError;
+use alloc::{format, vec::Vec};
 use bytes;
+use core::{error, fmt, ops};
 use nom;
 use serde;
-use std::{error, fmt, ops};
 use zerocopy::AsBytes;
 
+/// A key of [`ColorSegments::sort_with_key`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortKey {
+    /// See [`ColorSegments::sort_by_channel`].
+    Channel(color::Channel),
+    /// Hue of HSV. Colors without hue come last, achromatic colors followed by transparent colors.
+    Hue,
+    /// Relative luminance. Transparent colors count as black.
+    Luminance,
+}
+
+/// Whether [`ColorSegments::sort_with_key`] keeps the order of equal keys.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortStability {
+    /// Color segments with equal keys keep their order.
+    Stable,
+    /// Faster for large palettes, but the order of equal keys is unspecified.
+    Unstable,
+}
+
 /// ColorSegments
 ///
 ///
-#[derive(Debug, PartialEq, serde::Serialize)]
+#[derive(Debug, PartialEq, Clone, serde::Serialize)]
 pub struct ColorSegments {
     val: Vec<color_segment::ColorSegment>,
 }
@@ -32,6 +54,16 @@ impl ColorSegments {
         }
     }
 
+    /// Create empty ColorSegments with reserved capacity.
+    ///
+    /// # Note
+    /// The cls file requires at least one color segment, push one before serializing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        ColorSegments {
+            val: Vec::with_capacity(capacity),
+        }
+    }
+
     pub fn remove(
         &mut self,
         index: usize,
@@ -43,9 +75,340 @@ impl ColorSegments {
         }
     }
 
+    /// Insert `color_segment` at `index`, shifting the later ones. `index` may be the length to append.
+    pub fn insert(
+        &mut self,
+        index: usize,
+        color_segment: color_segment::ColorSegment,
+    ) -> Result<(), ColorSegmentsError> {
+        if index <= self.val.len() {
+            self.val.insert(index, color_segment);
+            Ok(())
+        } else {
+            Err(ColorSegmentsError::InsertIndexError)
+        }
+    }
+
+    /// Move the color segment at `from` to `to`, shifting the ones between.
+    pub fn move_segment(&mut self, from: usize, to: usize) -> Result<(), ColorSegmentsError> {
+        if from < self.val.len() && to < self.val.len() {
+            let cs = self.val.remove(from);
+            self.val.insert(to, cs);
+            Ok(())
+        } else {
+            Err(ColorSegmentsError::MoveIndexError)
+        }
+    }
+
+    /// Swap the color segments at `a` and `b`.
+    pub fn swap(&mut self, a: usize, b: usize) -> Result<(), ColorSegmentsError> {
+        if a < self.val.len() && b < self.val.len() {
+            self.val.swap(a, b);
+            Ok(())
+        } else {
+            Err(ColorSegmentsError::SwapIndexError)
+        }
+    }
+
+    /// Insert a copy of the color segment at `index` right after it.
+    ///
+    /// The name of the copy gets a " copy" suffix, truncated to the limit of [`color_name::ColorName`].
+    pub fn duplicate(&mut self, index: usize) -> Result<(), ColorSegmentsError> {
+        let mut cs = self
+            .val
+            .get(index)
+            .cloned()
+            .ok_or(ColorSegmentsError::DuplicateIndexError)?;
+        if let Some(color_name) = cs.get_color_name_mut_ref() {
+            let copy_name = format!("{} copy", **color_name);
+            color_name.set_str_truncated(&copy_name);
+        }
+        self.val.insert(index + 1, cs);
+
+        Ok(())
+    }
+
+    /// Insert `steps` unnamed color segments right after `from_idx`, interpolating from its color to the one at `to_idx`.
+    ///
+    /// The colors are evenly spaced by [`color::Color::lerp`], excluding both ends, so they are opaque.
+    pub fn insert_ramp(
+        &mut self,
+        from_idx: usize,
+        to_idx: usize,
+        steps: usize,
+    ) -> Result<(), ColorSegmentsError> {
+        let (from, to) = match (self.val.get(from_idx), self.val.get(to_idx)) {
+            (Some(from), Some(to)) => (from.get_color_ref(), to.get_color_ref()),
+            _ => return Err(ColorSegmentsError::RampIndexError),
+        };
+
+        let ramp = (1..=steps)
+            .map(|step| {
+                let color = from.lerp(to, step as f32 / (steps + 1) as f32);
+                color_segment::ColorSegment::new(color, None)
+            })
+            .collect::<Vec<_>>();
+        self.val.splice(from_idx + 1..from_idx + 1, ramp);
+
+        Ok(())
+    }
+
+    /// Iterate the color segments whose color is opaque.
+    pub fn opaque_segments(&self) -> impl Iterator<Item = &color_segment::ColorSegment> {
+        self.val
+            .iter()
+            .filter(|cs| !cs.get_color_ref().get_transparency())
+    }
+
+    /// Iterate the color segments whose color is transparent.
+    pub fn transparent_segments(&self) -> impl Iterator<Item = &color_segment::ColorSegment> {
+        self.val
+            .iter()
+            .filter(|cs| cs.get_color_ref().get_transparency())
+    }
+
+    /// Split into (opaque, transparent) color segments, keeping the order.
+    ///
+    /// # Note
+    /// Either may be empty, push a color segment before serializing it, see [`ColorSegments::with_capacity`].
+    pub fn partition_transparency(self) -> (ColorSegments, ColorSegments) {
+        let (transparent, opaque) = self
+            .val
+            .into_iter()
+            .partition(|cs| cs.get_color_ref().get_transparency());
+
+        (
+            ColorSegments { val: opaque },
+            ColorSegments { val: transparent },
+        )
+    }
+
+    /// Returns the indices of the color segments whose color satisfies `pred`.
+    pub fn positions_matching(&self, pred: impl Fn(&color::Color) -> bool) -> Vec<usize> {
+        self.val
+            .iter()
+            .enumerate()
+            .filter(|(_, cs)| pred(cs.get_color_ref()))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Returns the first color segment named `name`. Segments without a name never match.
+    pub fn find_by_name(&self, name: &str) -> Option<&color_segment::ColorSegment> {
+        self.position_by_name(name).map(|idx| &self.val[idx])
+    }
+
+    /// Returns the index of the first color segment named `name`. Segments without a name never match.
+    pub fn position_by_name(&self, name: &str) -> Option<usize> {
+        self.val.iter().position(|cs| {
+            cs.get_color_name_ref()
+                .is_some_and(|color_name| **color_name == name)
+        })
+    }
+
+    /// Keep only the color segments whose color satisfies `f`.
+    ///
+    /// Returns the number of removed segments.
+    /// If no segment would be kept, nothing is removed and an error is returned.
+    pub fn retain_colors<F>(&mut self, mut f: F) -> Result<usize, ColorSegmentsError>
+    where
+        F: FnMut(&color::Color) -> bool,
+    {
+        let keep = self
+            .val
+            .iter()
+            .map(|cs| f(cs.get_color_ref()))
+            .collect::<Vec<_>>();
+        if !keep.contains(&true) {
+            return Err(ColorSegmentsError::RetainNothingError);
+        }
+
+        let mut keep = keep.into_iter();
+        let before = self.val.len();
+        self.val.retain(|_| keep.next().unwrap_or(true));
+
+        Ok(before - self.val.len())
+    }
+
     pub fn push(&mut self, color_segment: color_segment::ColorSegment) {
         self.val.push(color_segment)
     }
+
+    /// Remove color segments whose color equals an earlier one, ignoring the name.
+    ///
+    /// Colors are compared by [`Color::cls_eq`](color_segment::color::Color::cls_eq).
+    /// The first segment is always kept, so the color segments never become empty.
+    /// Returns the number of removed segments.
+    pub fn dedup_colors(&mut self) -> usize {
+        self.dedup_by_earlier(|cs, kept_cs| cs.get_color_ref().cls_eq(kept_cs.get_color_ref()))
+    }
+
+    /// Remove color segments whose color and name both equal an earlier one.
+    ///
+    /// See [`ColorSegments::dedup_colors`].
+    pub fn dedup_colors_and_names(&mut self) -> usize {
+        self.dedup_by_earlier(|cs, kept_cs| {
+            cs.get_color_ref().cls_eq(kept_cs.get_color_ref())
+                && cs.get_color_name_ref() == kept_cs.get_color_name_ref()
+        })
+    }
+
+    /// Remove color segments that are `same` as an earlier kept one, returning the number of removed segments.
+    ///
+    /// `same` gets the segment and a kept one, and may move values between them before it is removed.
+    fn dedup_by_earlier(
+        &mut self,
+        mut same: impl FnMut(&mut color_segment::ColorSegment, &mut color_segment::ColorSegment) -> bool,
+    ) -> usize {
+        let before = self.val.len();
+
+        let mut kept = Vec::<color_segment::ColorSegment>::with_capacity(before);
+        for mut cs in self.val.drain(..) {
+            if !kept.iter_mut().any(|kept_cs| same(&mut cs, kept_cs)) {
+                kept.push(cs);
+            }
+        }
+        self.val = kept;
+
+        before - self.val.len()
+    }
+
+    /// Remove color segments whose color equals an earlier one, ignoring the name.
+    ///
+    /// If the kept segment has no name, the name of the first removed duplicate with a name is moved to it.
+    /// Returns the number of removed segments.
+    pub fn dedup_colors_keep_name(&mut self) -> usize {
+        self.dedup_by_earlier(|cs, kept_cs| {
+            if !cs.get_color_ref().cls_eq(kept_cs.get_color_ref()) {
+                return false;
+            }
+            let kept_name = kept_cs.get_color_name_mut_ref();
+            if kept_name.is_none() {
+                *kept_name = cs.get_color_name_mut_ref().take();
+            }
+            true
+        })
+    }
+
+    /// Sort color segments in ascending order of the channel.
+    ///
+    /// The sort is stable. Channels are compared as written to the cls file,
+    /// so transparent colors count as 0 in every channel.
+    pub fn sort_by_channel(&mut self, channel: color::Channel) {
+        self.val
+            .sort_by_key(|cs| cs.get_color_ref().get_channel(channel));
+    }
+
+    /// Sort color segments in ascending order of `key`.
+    ///
+    /// With [`SortStability::Stable`], color segments with equal keys keep their order.
+    pub fn sort_with_key(&mut self, key: SortKey, stability: SortStability) {
+        use color_segment::ColorSegment;
+        use core::cmp::Ordering;
+
+        let hue_rank = |cs: &ColorSegment| {
+            let color = cs.get_color_ref();
+            match (color.get_transparency(), color.hue()) {
+                (false, Some(hue)) => (0, hue),
+                (false, None) => (1, 0.0),
+                (true, _) => (2, 0.0),
+            }
+        };
+        let luminance = |cs: &ColorSegment| {
+            let color = cs.get_color_ref();
+            if color.get_transparency() {
+                0.0
+            } else {
+                color.relative_luminance()
+            }
+        };
+        let compare = |cs: &ColorSegment, other_cs: &ColorSegment| -> Ordering {
+            match key {
+                SortKey::Channel(channel) => cs
+                    .get_color_ref()
+                    .get_channel(channel)
+                    .cmp(&other_cs.get_color_ref().get_channel(channel)),
+                SortKey::Hue => {
+                    let ((group, hue), (other_group, other_hue)) =
+                        (hue_rank(cs), hue_rank(other_cs));
+                    group.cmp(&other_group).then(hue.total_cmp(&other_hue))
+                }
+                SortKey::Luminance => luminance(cs).total_cmp(&luminance(other_cs)),
+            }
+        };
+
+        match stability {
+            SortStability::Stable => self.val.sort_by(compare),
+            SortStability::Unstable => self.val.sort_unstable_by(compare),
+        }
+    }
+
+    /// Sort color segments stably in ascending order of hue.
+    ///
+    /// See [`SortKey::Hue`].
+    pub fn sort_by_hue(&mut self) {
+        self.sort_with_key(SortKey::Hue, SortStability::Stable);
+    }
+
+    /// Sort color segments stably in ascending order of relative luminance.
+    ///
+    /// See [`SortKey::Luminance`].
+    pub fn sort_by_luminance(&mut self) {
+        self.sort_with_key(SortKey::Luminance, SortStability::Stable);
+    }
+
+    /// Sort color segments stably in ascending order of the color name. Unnamed color segments come last.
+    pub fn sort_by_name(&mut self) {
+        self.val.sort_by(|cs, other_cs| {
+            match (cs.get_color_name_ref(), other_cs.get_color_name_ref()) {
+                (Some(name), Some(other_name)) => name.cmp(other_name),
+                (name, other_name) => other_name.is_some().cmp(&name.is_some()),
+            }
+        });
+    }
+
+    /// Remove consecutive color segments within `threshold` of the previous kept one by `metric`, ignoring the name.
+    ///
+    /// Transparent colors are only merged into transparent colors, see [`color::Color::distance_by`].
+    /// The first segment of each run is kept. Returns the number of removed segments.
+    pub fn dedup_similar_adjacent(
+        &mut self,
+        metric: color::DistanceMetric,
+        threshold: f32,
+    ) -> usize {
+        let before = self.val.len();
+        self.val.dedup_by(|cs, kept| {
+            cs.get_color_ref().distance_by(kept.get_color_ref(), metric) <= threshold
+        });
+
+        before - self.val.len()
+    }
+
+    /// Returns the index and the color segment whose color is nearest to `target` by [`color::Color::distance`].
+    ///
+    /// Transparent colors are only searched if `target` is transparent, and then opaque colors are not.
+    /// Returns None if there is no such color segment.
+    pub fn nearest(&self, target: &color::Color) -> Option<(usize, &color_segment::ColorSegment)> {
+        self.val
+            .iter()
+            .enumerate()
+            .filter(|(_, cs)| cs.get_color_ref().get_transparency() == target.get_transparency())
+            .map(|(idx, cs)| (idx, cs, cs.get_color_ref().distance(target)))
+            .min_by(|(_, _, dist), (_, _, other_dist)| dist.total_cmp(other_dist))
+            .map(|(idx, cs, _)| (idx, cs))
+    }
+
+    /// Remove consecutive color segments with the same color, ignoring the name.
+    ///
+    /// Like [`Vec::dedup`], only adjacent duplicates are removed, so this only makes sense after sorting by color.
+    /// The first segment of each run is kept. Returns the number of removed segments.
+    pub fn dedup_adjacent(&mut self) -> usize {
+        let before = self.val.len();
+        self.val
+            .dedup_by(|cs, prev| cs.get_color_ref().cls_eq(prev.get_color_ref()));
+
+        before - self.val.len()
+    }
 }
 
 impl common::ClsSize for ColorSegments {
@@ -76,43 +439,85 @@ impl common::ExtendBytesMut for ColorSegments {
         // extend color segments
         self.iter().for_each(|cs| cs.extend_bytes(extended));
     }
+
+    #[cfg(feature = "std")]
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use common::ClsSize;
+        writer.write_all((self.len() as u32).as_bytes())?;
+        writer.write_all(self.size_contents_in_cls().as_bytes())?;
+        self.iter().try_for_each(|cs| cs.write_to(writer))
+    }
 }
 
-impl common::TryFromBytes for ColorSegments {
-    fn try_from_bytes(input: &[u8]) -> nom::IResult<&[u8], Self>
-    where
-        Self: Sized,
-    {
-        use nom::multi::fold_many0;
+/// Size of an unnamed color segment, the smallest one in the cls file.
+const MIN_COLOR_SEGMENT_SIZE: usize = 4 // u32 of color segment byte size
+    + 4 // color
+    + 4; // u32 of color name flag
+
+impl ColorSegments {
+    /// Same as [`common::TryFromBytes::try_from_bytes_with`],
+    /// but an error comes with the offset of the section that failed from the start of `input`.
+    ///
+    /// # Note
+    /// The offset is of the color segment that failed, or 0 if the color segments as a whole are wrong.
+    pub(crate) fn try_from_bytes_with_offset<'a>(
+        input: &'a [u8],
+        options: &common::ParseOptions,
+    ) -> common::OffsetResult<'a, Self> {
+        use common::TryFromBytes;
         use nom::number::complete::le_u32;
-        use nom::{error::Error, error::ErrorKind::Fail, error::FromExternalError, Err::Failure};
+        use nom::Err::{Error, Failure};
+        let whole = |err| (0, err);
         // get number of colors
-        let (input, num_colors) = le_u32(input)?;
-        // ignore color segments bytes
-        let (input, _) = le_u32(input)?;
-        // get colorsegments
-        let (input, color_segment_vec) = fold_many0(
-            color_segment::ColorSegment::try_from_bytes,
-            Vec::new,
-            |mut acc: Vec<color_segment::ColorSegment>, item| {
-                acc.push(item);
-                acc
-            },
-        )(input)?;
+        let (input, num_colors) = le_u32(input)
+            .map_err(common::unexpected_eof("ColorSegments", 4))
+            .map_err(whole)?;
+        // get color segments bytes
+        let (input, size) = le_u32(input)
+            .map_err(common::unexpected_eof("ColorSegments", 4))
+            .map_err(whole)?;
+        let contents = input;
+        let offset = |rest: &[u8]| 8 + contents.len() - rest.len();
+        // fail fast on a number of colors that cannot fit in the input
+        let min_size = (num_colors as usize).saturating_mul(MIN_COLOR_SEGMENT_SIZE);
+        if min_size > input.len() {
+            return Err(whole(Failure(ClsError::UnexpectedEof {
+                expected: min_size,
+                section: "ColorSegments",
+            })));
+        }
+        // get colorsegments, leaving the bytes after the declared ones
+        let mut input = input;
+        let mut color_segment_vec = Vec::new();
+        while color_segment_vec.len() < num_colors as usize {
+            match color_segment::ColorSegment::try_from_bytes_with(input, options) {
+                Ok((next, color_segment)) => {
+                    color_segment_vec.push(color_segment);
+                    input = next;
+                }
+                Err(Error(_)) => break,
+                Err(err) => return Err((offset(input), err)),
+            }
+        }
 
         if color_segment_vec.is_empty() {
-            return Err(Failure(Error::from_external_error(
-                input,
-                Fail,
-                "Color segments is empty!", // Colorsegments Error
-            )));
+            return Err(whole(Failure(ClsError::EmptySegments)));
+        } else if (color_segment_vec.len() as u32) < num_colors && !input.is_empty() {
+            // the rest is neither the declared color segments nor the end of input
+            return Err((
+                offset(input),
+                Failure(ClsError::MalformedSegment {
+                    offset: offset(input),
+                }),
+            ));
         } else if color_segment_vec.len() as u32 != num_colors {
-            return Err(Failure(Error::from_external_error(
-                input,
-                Fail,
-                "Error in read color segments", // Colorsegments Error
-            )));
+            return Err(whole(Failure(ClsError::SegmentCountMismatch {
+                expected: num_colors,
+                found: color_segment_vec.len(),
+            })));
         }
+        common::check_size("ColorSegments", size, contents.len() - input.len(), options)
+            .map_err(whole)?;
         Ok((
             input,
             ColorSegments {
@@ -122,6 +527,41 @@ impl common::TryFromBytes for ColorSegments {
     }
 }
 
+impl common::TryFromBytes for ColorSegments {
+    fn try_from_bytes_with<'a>(
+        input: &'a [u8],
+        options: &common::ParseOptions,
+    ) -> nom::IResult<&'a [u8], Self, ClsError>
+    where
+        Self: Sized,
+    {
+        Self::try_from_bytes_with_offset(input, options).map_err(|(_, err)| err)
+    }
+}
+
+/// Accepts both the serialized struct form and a plain array, which must not be empty.
+impl<'de> serde::Deserialize<'de> for ColorSegments {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Seq(Vec<color_segment::ColorSegment>),
+            Struct {
+                val: Vec<color_segment::ColorSegment>,
+            },
+        }
+
+        let (Repr::Seq(val) | Repr::Struct { val }) = Repr::deserialize(deserializer)?;
+        if val.is_empty() {
+            return Err(serde::de::Error::custom(ClsError::EmptySegments));
+        }
+        Ok(ColorSegments { val })
+    }
+}
+
 impl ops::Deref for ColorSegments {
     type Target = Vec<color_segment::ColorSegment>;
     fn deref(&self) -> &Self::Target {
@@ -138,17 +578,530 @@ impl ops::DerefMut for ColorSegments {
 #[derive(Debug)]
 pub enum ColorSegmentsError {
     RemoveIndexError,
+    InsertIndexError,
+    MoveIndexError,
+    SwapIndexError,
+    DuplicateIndexError,
+    RampIndexError,
+    RetainNothingError,
 }
 
 impl fmt::Display for ColorSegmentsError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", {
             use ColorSegmentsError::*;
             match self {
                 RemoveIndexError => "Invalid Index, cannot remove.",
+                InsertIndexError => "Invalid Index, cannot insert.",
+                MoveIndexError => "Invalid Index, cannot move.",
+                SwapIndexError => "Invalid Index, cannot swap.",
+                DuplicateIndexError => "Invalid Index, cannot duplicate.",
+                RampIndexError => "Invalid Index, cannot insert a ramp.",
+                RetainNothingError => "No color segment is left, cannot remove.",
             }
         })
     }
 }
 
 impl error::Error for ColorSegmentsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::color_segment::ColorSegment;
+    use super::common::*;
+    use super::ColorSegments;
+    use crate::error::ClsError;
+
+    #[test]
+    fn truncated_test() {
+        let mut color_segments = ColorSegments::new();
+        color_segments.push(ColorSegment::with_val(1, 128, 255, false, None).unwrap());
+        let mut ex_bytes = bytes::BytesMut::new();
+        color_segments.extend_bytes(&mut ex_bytes);
+
+        // Truncated in the last color segment
+        let err = ColorSegments::try_from_bytes(&ex_bytes[..ex_bytes.len() - 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Failure(ClsError::UnexpectedEof {
+                expected: 4,
+                section: "ColorSegment"
+            })
+        ));
+
+        // Truncated in the header
+        let err = ColorSegments::try_from_bytes(&ex_bytes[..7]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Error(ClsError::UnexpectedEof {
+                expected: 4,
+                section: "ColorSegments"
+            })
+        ));
+
+        // Truncated between color segments
+        let err = ColorSegments::try_from_bytes(&ex_bytes[..ex_bytes.len() - 12]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Failure(ClsError::SegmentCountMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+
+        // Truncated in the size header of the last color segment
+        let err = ColorSegments::try_from_bytes(&ex_bytes[..ex_bytes.len() - 10]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Failure(ClsError::MalformedSegment { offset: 34 })
+        ));
+
+        // Too many colors for the input
+        let mut huge_count = ex_bytes.to_vec();
+        huge_count[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = ColorSegments::try_from_bytes(&huge_count).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Failure(ClsError::UnexpectedEof {
+                section: "ColorSegments",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn dedup_adjacent_test() {
+        let mut color_segments = ColorSegments::with_capacity(6);
+        for (red, name) in [
+            (0, Some("Black")),
+            (0, None),
+            (128, None),
+            (255, None),
+            (255, Some("Red")),
+            (0, None),
+        ] {
+            color_segments.push(ColorSegment::with_val(red, 0, 0, false, name).unwrap());
+        }
+
+        assert_eq!(color_segments.dedup_adjacent(), 2);
+        assert_eq!(
+            *color_segments,
+            vec![
+                ColorSegment::with_val(0, 0, 0, false, Some("Black")).unwrap(),
+                ColorSegment::with_val(128, 0, 0, false, None).unwrap(),
+                ColorSegment::with_val(255, 0, 0, false, None).unwrap(),
+                ColorSegment::with_val(0, 0, 0, false, None).unwrap(),
+            ]
+        );
+        assert_eq!(color_segments.dedup_adjacent(), 0);
+
+        // transparent colors are compared as written to the cls file
+        color_segments.push(ColorSegment::with_val(255, 0, 0, true, None).unwrap());
+        color_segments.push(ColorSegment::with_val(0, 255, 0, true, None).unwrap());
+        assert_eq!(color_segments.dedup_adjacent(), 1);
+    }
+
+    #[test]
+    fn sort_by_channel_test() {
+        use super::color::Channel;
+
+        let mut color_segments = ColorSegments::new();
+        for (red, green) in [(255, 0), (128, 255), (0, 128), (128, 0)] {
+            color_segments.push(ColorSegment::with_val(red, green, 0, false, None).unwrap());
+        }
+
+        color_segments.sort_by_channel(Channel::Red);
+        let reds = color_segments
+            .iter()
+            .map(|cs| cs.get_color_ref().get_channel(Channel::Red))
+            .collect::<Vec<_>>();
+        assert_eq!(reds, vec![0, 0, 128, 128, 255]);
+        // transparent Color0 stays first among equal keys
+        assert!(color_segments[0].get_color_ref().get_transparency());
+        assert_eq!(color_segments[2].get_color_ref().get_rgb(), (128, 255, 0));
+
+        color_segments.sort_by_channel(Channel::Alpha);
+        assert!(color_segments[0].get_color_ref().get_transparency());
+    }
+
+    #[test]
+    fn dedup_colors_test() {
+        let setup = || {
+            let mut color_segments = ColorSegments::with_capacity(4);
+            for (red, name) in [
+                (255, None),
+                (0, Some("Black")),
+                (255, Some("Red")),
+                (0, Some("Black")),
+            ] {
+                color_segments.push(ColorSegment::with_val(red, 0, 0, false, name).unwrap());
+            }
+            color_segments
+        };
+
+        let mut color_segments = setup();
+        assert_eq!(color_segments.dedup_colors(), 2);
+        assert_eq!(
+            *color_segments,
+            vec![
+                ColorSegment::with_val(255, 0, 0, false, None).unwrap(),
+                ColorSegment::with_val(0, 0, 0, false, Some("Black")).unwrap(),
+            ]
+        );
+
+        let mut color_segments = setup();
+        assert_eq!(color_segments.dedup_colors_and_names(), 1);
+        assert_eq!(color_segments.len(), 3);
+
+        // all the same
+        let mut color_segments = ColorSegments::with_capacity(3);
+        for _ in 0..3 {
+            color_segments.push(ColorSegment::with_val(1, 2, 3, false, None).unwrap());
+        }
+        assert_eq!(color_segments.dedup_colors(), 2);
+        assert_eq!(color_segments.len(), 1);
+
+        // transparent colors are the same in the cls file, whatever the RGB and serialize mode
+        let mut color_segments = ColorSegments::with_capacity(2);
+        color_segments.push(ColorSegment::with_val(0, 0, 0, true, None).unwrap());
+        let mut hidden_rgb = ColorSegment::with_val(255, 128, 0, true, None).unwrap();
+        hidden_rgb.get_color_mut_ref().set_serialize_mode_hex();
+        color_segments.push(hidden_rgb);
+        assert_eq!(color_segments.clone().dedup_colors(), 1);
+        assert_eq!(color_segments.clone().dedup_colors_and_names(), 1);
+        assert_eq!(color_segments.dedup_colors_keep_name(), 1);
+    }
+
+    #[test]
+    fn dedup_colors_keep_name_test() {
+        let mut color_segments = ColorSegments::with_capacity(5);
+        for (red, name) in [
+            (255, None),
+            (0, Some("Black")),
+            (255, Some("Red")),
+            (255, Some("Crimson")),
+            (0, None),
+        ] {
+            color_segments.push(ColorSegment::with_val(red, 0, 0, false, name).unwrap());
+        }
+
+        assert_eq!(color_segments.dedup_colors_keep_name(), 3);
+        assert_eq!(
+            *color_segments,
+            vec![
+                ColorSegment::with_val(255, 0, 0, false, Some("Red")).unwrap(),
+                ColorSegment::with_val(0, 0, 0, false, Some("Black")).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_with_key_test() {
+        use super::{SortKey, SortStability};
+
+        let mut color_segments = ColorSegments::new();
+        for hex in ["#0000FF", "#808080", "#FF0000", "#00FF00"] {
+            color_segments.push(ColorSegment::new(
+                super::color::Color::new_with_hex_color(hex, false).unwrap(),
+                None,
+            ));
+        }
+
+        color_segments.sort_with_key(SortKey::Hue, SortStability::Stable);
+        let hexes = color_segments
+            .iter()
+            .map(|cs| cs.get_color_ref().get_hex_color(true))
+            .collect::<Vec<_>>();
+        // gray, then transparent Color0 have no hue
+        assert_eq!(hexes[..4], ["#FF0000", "#00FF00", "#0000FF", "#808080"]);
+        assert!(color_segments[4].get_color_ref().get_transparency());
+
+        color_segments.sort_with_key(SortKey::Luminance, SortStability::Unstable);
+        let hexes = color_segments
+            .iter()
+            .map(|cs| cs.get_color_ref().get_hex_color(true))
+            .collect::<Vec<_>>();
+        assert_eq!(hexes[1..], ["#0000FF", "#FF0000", "#808080", "#00FF00"]);
+        assert!(color_segments[0].get_color_ref().get_transparency());
+    }
+
+    #[test]
+    fn sort_by_test() {
+        let mut color_segments = ColorSegments::new();
+        for (hex, name) in [
+            ("#0000FF", Some("Blue")),
+            ("#808080", None),
+            ("#FF0000", Some("Red")),
+            ("#00FF00", Some("Green")),
+        ] {
+            color_segments.push(ColorSegment::new(
+                super::color::Color::new_with_hex_color(hex, false).unwrap(),
+                name.map(|name| super::color_name::ColorName::with_str(name).unwrap()),
+            ));
+        }
+        let hexes = |color_segments: &ColorSegments| {
+            color_segments
+                .iter()
+                .map(|cs| cs.get_color_ref().get_hex_color(true))
+                .collect::<Vec<_>>()
+        };
+
+        color_segments.sort_by_hue();
+        // gray, then transparent Color0
+        assert_eq!(
+            hexes(&color_segments),
+            ["#FF0000", "#00FF00", "#0000FF", "#808080", "#000000"]
+        );
+
+        color_segments.sort_by_luminance();
+        assert_eq!(
+            hexes(&color_segments),
+            ["#000000", "#0000FF", "#FF0000", "#808080", "#00FF00"]
+        );
+
+        color_segments.sort_by_name();
+        let names = color_segments
+            .iter()
+            .map(|cs| cs.get_color_name_ref().map(|name| name.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                Some("Blue".to_string()),
+                Some("Color0".to_string()),
+                Some("Green".to_string()),
+                Some("Red".to_string()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_matching_test() {
+        let mut color_segments = ColorSegments::new();
+        for (red, green, blue) in [(255, 255, 255), (16, 16, 16), (250, 250, 240), (0, 0, 255)] {
+            color_segments.push(ColorSegment::with_val(red, green, blue, false, None).unwrap());
+        }
+
+        let near_white = color_segments.positions_matching(|color| {
+            !color.get_transparency() && color.relative_luminance() > 0.8
+        });
+        assert_eq!(near_white, vec![1, 3]);
+        assert!(color_segments
+            .positions_matching(|color| color.relative_luminance() > 1.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn sort_with_key_stable_test() {
+        use super::{SortKey, SortStability};
+
+        // all reds have the hue 0, and greens have the hue 120
+        let mut color_segments = ColorSegments::with_capacity(64);
+        for val in 1..=64u8 {
+            let (red, green) = if val % 3 == 0 { (0, val) } else { (val, 0) };
+            color_segments.push(ColorSegment::with_val(red, green, 0, false, None).unwrap());
+        }
+
+        color_segments.sort_with_key(SortKey::Hue, SortStability::Stable);
+        let vals = color_segments
+            .iter()
+            .map(|cs| {
+                let (red, green, _) = cs.get_color_ref().get_rgb();
+                red.max(green)
+            })
+            .collect::<Vec<_>>();
+        let reds = (1..=64u8).filter(|val| val % 3 != 0);
+        let greens = (1..=64u8).filter(|val| val % 3 == 0);
+        assert_eq!(vals, reds.chain(greens).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn find_by_name_test() {
+        let mut color_segments = ColorSegments::new();
+        color_segments.push(ColorSegment::with_val(1, 0, 0, false, None).unwrap());
+        color_segments.push(ColorSegment::with_val(2, 0, 0, false, Some("Red")).unwrap());
+        color_segments.push(ColorSegment::with_val(3, 0, 0, false, Some("Red")).unwrap());
+
+        assert_eq!(color_segments.position_by_name("Color0"), Some(0));
+        assert_eq!(color_segments.position_by_name("Red"), Some(2));
+        assert_eq!(
+            color_segments
+                .find_by_name("Red")
+                .map(|cs| cs.get_color_ref().get_rgb()),
+            Some((2, 0, 0))
+        );
+        assert_eq!(color_segments.position_by_name("red"), None);
+        assert!(color_segments.find_by_name("").is_none());
+    }
+
+    #[test]
+    fn insert_ramp_test() {
+        use super::ColorSegmentsError;
+
+        let mut color_segments = ColorSegments::with_capacity(5);
+        color_segments.push(ColorSegment::with_val(0, 0, 0, false, Some("Black")).unwrap());
+        color_segments.push(ColorSegment::with_val(200, 100, 0, false, Some("Orange")).unwrap());
+
+        color_segments.insert_ramp(0, 1, 3).unwrap();
+        assert_eq!(
+            color_segments
+                .iter()
+                .map(|cs| cs.get_color_ref().get_rgb())
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 0, 0),
+                (50, 25, 0),
+                (100, 50, 0),
+                (150, 75, 0),
+                (200, 100, 0)
+            ]
+        );
+        assert!(color_segments[2].get_color_name_ref().is_none());
+
+        color_segments.insert_ramp(4, 0, 0).unwrap();
+        assert_eq!(color_segments.len(), 5);
+        assert!(matches!(
+            color_segments.insert_ramp(0, 5, 1),
+            Err(ColorSegmentsError::RampIndexError)
+        ));
+    }
+
+    #[test]
+    fn transparency_split_test() {
+        let mut color_segments = ColorSegments::new();
+        color_segments.push(ColorSegment::with_val(1, 2, 3, false, None).unwrap());
+        color_segments.push(ColorSegment::with_val(4, 5, 6, true, None).unwrap());
+        color_segments.push(ColorSegment::with_val(7, 8, 9, false, None).unwrap());
+
+        let rgb = |cs: &ColorSegment| cs.get_color_ref().get_rgb();
+        assert_eq!(
+            color_segments
+                .opaque_segments()
+                .map(rgb)
+                .collect::<Vec<_>>(),
+            vec![(1, 2, 3), (7, 8, 9)]
+        );
+        assert_eq!(
+            color_segments
+                .transparent_segments()
+                .map(rgb)
+                .collect::<Vec<_>>(),
+            vec![(0, 0, 0), (4, 5, 6)]
+        );
+
+        let (opaque, transparent) = color_segments.clone().partition_transparency();
+        assert!(opaque.iter().eq(color_segments.opaque_segments()));
+        assert!(transparent.iter().eq(color_segments.transparent_segments()));
+
+        let (opaque, transparent) = opaque.partition_transparency();
+        assert_eq!(opaque.len(), 2);
+        assert!(transparent.is_empty());
+    }
+
+    #[test]
+    fn duplicate_test() {
+        use super::ColorSegmentsError;
+
+        let mut color_segments = ColorSegments::new();
+        color_segments.push(ColorSegment::with_val(1, 2, 3, false, None).unwrap());
+        color_segments.push(ColorSegment::with_val(4, 5, 6, false, Some(&"a".repeat(62))).unwrap());
+
+        color_segments.duplicate(0).unwrap();
+        assert_eq!(
+            color_segments[1],
+            ColorSegment::with_val(0, 0, 0, true, Some("Color0 copy")).unwrap()
+        );
+        color_segments.duplicate(2).unwrap();
+        assert_eq!(color_segments[3], color_segments[2]);
+        color_segments.duplicate(4).unwrap();
+        assert_eq!(
+            color_segments[5],
+            ColorSegment::with_val(4, 5, 6, false, Some(&format!("{} c", "a".repeat(62)))).unwrap()
+        );
+
+        assert!(matches!(
+            color_segments.duplicate(6),
+            Err(ColorSegmentsError::DuplicateIndexError)
+        ));
+        assert_eq!(color_segments.len(), 6);
+    }
+
+    #[test]
+    fn nearest_test() {
+        use super::color::Color;
+
+        let mut color_segments = ColorSegments::with_capacity(4);
+        assert!(color_segments
+            .nearest(&Color::new(0, 0, 0, false))
+            .is_none());
+        color_segments.push(ColorSegment::with_val(250, 250, 250, true, None).unwrap());
+        color_segments.push(ColorSegment::with_val(255, 0, 0, false, Some("Red")).unwrap());
+        color_segments.push(ColorSegment::with_val(0, 0, 255, false, Some("Blue")).unwrap());
+
+        let (idx, cs) = color_segments
+            .nearest(&Color::new(200, 30, 60, false))
+            .unwrap();
+        assert_eq!(idx, 1);
+        assert_eq!(cs.get_color_ref().get_rgb(), (255, 0, 0));
+        // the transparent white is not nearest to white
+        assert_eq!(
+            color_segments
+                .nearest(&Color::new(255, 255, 255, false))
+                .map(|(idx, _)| idx),
+            Some(1)
+        );
+        assert_eq!(
+            color_segments
+                .nearest(&Color::new(0, 0, 0, true))
+                .map(|(idx, _)| idx),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn insert_move_swap_test() {
+        use super::ColorSegmentsError;
+
+        let mut color_segments = ColorSegments::with_capacity(4);
+        let reds = |color_segments: &ColorSegments| {
+            color_segments
+                .iter()
+                .map(|cs| cs.get_color_ref().get_rgb().0)
+                .collect::<Vec<_>>()
+        };
+        for red in [0, 2] {
+            color_segments.push(ColorSegment::with_val(red, 0, 0, false, None).unwrap());
+        }
+
+        color_segments
+            .insert(1, ColorSegment::with_val(1, 0, 0, false, None).unwrap())
+            .unwrap();
+        color_segments
+            .insert(3, ColorSegment::with_val(3, 0, 0, false, None).unwrap())
+            .unwrap();
+        assert_eq!(reds(&color_segments), vec![0, 1, 2, 3]);
+
+        color_segments.move_segment(0, 2).unwrap();
+        assert_eq!(reds(&color_segments), vec![1, 2, 0, 3]);
+        color_segments.move_segment(3, 0).unwrap();
+        assert_eq!(reds(&color_segments), vec![3, 1, 2, 0]);
+        color_segments.swap(0, 3).unwrap();
+        assert_eq!(reds(&color_segments), vec![0, 1, 2, 3]);
+
+        let cs = ColorSegment::with_val(5, 0, 0, false, None).unwrap();
+        assert!(matches!(
+            color_segments.insert(5, cs),
+            Err(ColorSegmentsError::InsertIndexError)
+        ));
+        assert!(matches!(
+            color_segments.move_segment(1, 4),
+            Err(ColorSegmentsError::MoveIndexError)
+        ));
+        assert!(matches!(
+            color_segments.swap(4, 0),
+            Err(ColorSegmentsError::SwapIndexError)
+        ));
+        assert_eq!(reds(&color_segments), vec![0, 1, 2, 3]);
+    }
+}
@@ -6,13 +6,15 @@ pub mod color;
 pub mod color_name;
 
 use crate::colorset::common;
+use crate::error::ClsError;
+use alloc::string::String;
 use bytes;
 use nom;
 use serde;
 use zerocopy::AsBytes;
 
 /// ColorSegment
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ColorSegment {
     color: color::Color,
     color_name: Option<color_name::ColorName>,
@@ -23,6 +25,15 @@ impl ColorSegment {
         ColorSegment { color, color_name }
     }
 
+    /// Create a named ColorSegment from utf16le name bytes, see [`color_name::ColorName::from_utf16le_bytes`].
+    pub fn with_raw_name(
+        color: color::Color,
+        name_bytes: &[u8],
+    ) -> Result<Self, color_name::ColorNameError> {
+        color_name::ColorName::from_utf16le_bytes(name_bytes)
+            .map(|cn| ColorSegment::new(color, Some(cn)))
+    }
+
     pub fn with_val(
         red: u8,
         green: u8,
@@ -40,6 +51,37 @@ impl ColorSegment {
         }
     }
 
+    pub fn get_color_ref(&self) -> &color::Color {
+        &self.color
+    }
+
+    pub fn get_color_name_ref(&self) -> Option<&color_name::ColorName> {
+        self.color_name.as_ref()
+    }
+
+    /// Same as [`ColorSegment::get_color_ref`].
+    pub fn color(&self) -> &color::Color {
+        &self.color
+    }
+
+    /// Returns the color name as str, or None if the color segment has no name.
+    pub fn color_name(&self) -> Option<&str> {
+        self.color_name.as_deref().map(String::as_str)
+    }
+
+    /// Returns the bytes of this color segment in the cls file, including the size header.
+    pub fn to_bytes(&self) -> bytes::Bytes {
+        use common::ClsSection;
+        self.as_cls_bytes()
+    }
+
+    /// Returns true if both color segments are written as the same bytes in the cls file.
+    ///
+    /// See [`color::Color::cls_eq`].
+    pub fn cls_eq(&self, other: &ColorSegment) -> bool {
+        self.color.cls_eq(&other.color) && self.color_name == other.color_name
+    }
+
     pub fn get_color_mut_ref(&mut self) -> &mut color::Color {
         &mut self.color
     }
@@ -64,8 +106,8 @@ impl PartialEq for ColorSegment {
 }
 
 impl common::ClsSize for ColorSegment {
-    fn size_in_cls(&self) -> u32 {
-        4 + self.size_contents_in_cls()
+    fn size_header(&self) -> common::SizeHeader {
+        common::SizeHeader::U32
     }
 
     fn size_contents_in_cls(&self) -> u32 {
@@ -83,9 +125,9 @@ impl common::ClsSize for ColorSegment {
 
 impl common::ExtendBytesMut for ColorSegment {
     fn extend_bytes(&self, extended: &mut bytes::BytesMut) {
-        use common::ClsSize;
+        use common::ClsSection;
         // Extend Size Header
-        extended.extend_from_slice(self.size_contents_in_cls().as_bytes());
+        self.extend_size_header(extended);
 
         // Extend Color
         self.color.extend_bytes(extended);
@@ -104,33 +146,40 @@ impl common::ExtendBytesMut for ColorSegment {
 }
 
 impl common::TryFromBytes for ColorSegment {
-    fn try_from_bytes(input: &[u8]) -> nom::IResult<&[u8], Self>
+    fn try_from_bytes_with<'a>(
+        input: &'a [u8],
+        options: &common::ParseOptions,
+    ) -> nom::IResult<&'a [u8], Self, ClsError>
     where
         Self: Sized,
     {
         use nom::number::complete::le_u32;
+        use nom::Err::{Error, Failure};
+        // Once the size header is read, a failure is not recoverable.
+        let cut = |err| match err {
+            Error(err) => Failure(err),
+            err => err,
+        };
 
-        let (input, _) = le_u32(input)?;
-        let (input, color) = color::Color::try_from_bytes(input)?;
-        let (input, exists_color_name) = le_u32(input)?;
-        if exists_color_name == 1 {
-            let (input, color_name) = color_name::ColorName::try_from_bytes(input)?;
-            Ok((
-                input,
-                ColorSegment {
-                    color,
-                    color_name: Some(color_name),
-                },
-            ))
-        } else {
-            Ok((
-                input,
-                ColorSegment {
-                    color,
-                    color_name: None,
-                },
-            ))
+        let (input, size) = le_u32(input).map_err(common::unexpected_eof("ColorSegment", 4))?;
+        let contents = input;
+        let (input, color) = color::Color::try_from_bytes_with(input, options).map_err(cut)?;
+        let (input, exists_color_name) = le_u32(input)
+            .map_err(common::unexpected_eof("ColorSegment", 4))
+            .map_err(cut)?;
+        if options.strict_name_flag && exists_color_name > 1 {
+            return Err(Failure(ClsError::InvalidNameFlag(exists_color_name)));
         }
+        let (input, color_name) = if exists_color_name == 1 {
+            let (input, color_name) =
+                color_name::ColorName::try_from_bytes_with(input, options).map_err(cut)?;
+            (input, Some(color_name))
+        } else {
+            (input, None)
+        };
+        common::check_size("ColorSegment", size, contents.len() - input.len(), options)?;
+
+        Ok((input, ColorSegment { color, color_name }))
     }
 }
 
@@ -178,4 +227,130 @@ mod tests {
             ColorSegment::try_from_bytes(ex_bytes.as_ref()).unwrap();
         assert_eq!(de_color_segment_no_name, color_segment_no_name);
     }
+
+    #[test]
+    fn accessors_test() {
+        use setup::*;
+
+        let color_segment =
+            ColorSegment::new(color_setup(false), Some(color_name_setup("TESTCOLOR")));
+        assert_eq!(color_segment.color(), &color_setup(false));
+        assert_eq!(color_segment.color_name(), Some("TESTCOLOR"));
+        assert_eq!(
+            ColorSegment::new(color_setup(true), None).color_name(),
+            None
+        );
+    }
+
+    #[test]
+    fn with_raw_name_test() {
+        use setup::*;
+
+        let raw_name = "TESTCOLOR"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ColorSegment::with_raw_name(color_setup(false), &raw_name).unwrap(),
+            ColorSegment::new(color_setup(false), Some(color_name_setup("TESTCOLOR")))
+        );
+        assert!(ColorSegment::with_raw_name(color_setup(false), &raw_name[1..]).is_err());
+    }
+
+    #[test]
+    fn truncated_test() {
+        use crate::error::ClsError;
+        use setup::*;
+
+        // Truncated in color name
+        let color_segment = ColorSegment::new(color_setup(false), Some(color_name_setup("TEST")));
+        let mut ex_bytes = bytes::BytesMut::new();
+        color_segment.extend_bytes(&mut ex_bytes);
+        let err = ColorSegment::try_from_bytes(&ex_bytes[..ex_bytes.len() - 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Failure(ClsError::UnexpectedEof {
+                expected: 8,
+                section: "ColorName"
+            })
+        ));
+
+        // Truncated in color name flag
+        let color_segment_no_name = ColorSegment::new(color_setup(false), None);
+        let mut ex_bytes = bytes::BytesMut::new();
+        color_segment_no_name.extend_bytes(&mut ex_bytes);
+        let err = ColorSegment::try_from_bytes(&ex_bytes[..ex_bytes.len() - 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Failure(ClsError::UnexpectedEof {
+                expected: 4,
+                section: "ColorSegment"
+            })
+        ));
+    }
+
+    #[test]
+    fn to_bytes_test() {
+        use setup::*;
+
+        let named = ColorSegment::new(color_setup(false), Some(color_name_setup("AB")));
+        assert_eq!(
+            named.to_bytes().as_ref(),
+            [
+                14, 0, 0, 0, // size header
+                1, 128, 255, 0xFF, // color
+                1, 0, 0, 0, // color name flag
+                4, 0, b'A', 0, b'B', 0, // color name
+            ]
+        );
+
+        let unnamed = ColorSegment::new(color_setup(true), None);
+        assert_eq!(
+            unnamed.to_bytes().as_ref(),
+            [
+                8, 0, 0, 0, // size header
+                0, 0, 0, 0, // color
+                0, 0, 0, 0, // color name flag
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_options_test() {
+        use crate::colorset::common::ParseOptions;
+        use crate::error::ClsError;
+
+        let cs = ColorSegment::with_val(1, 128, 255, false, None).unwrap();
+        let mut cs_bytes = cs.to_bytes().to_vec();
+
+        // broken size header
+        cs_bytes[0] += 1;
+        assert!(ColorSegment::try_from_bytes(&cs_bytes).is_ok());
+        let options = ParseOptions {
+            check_sizes: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ColorSegment::try_from_bytes_with(&cs_bytes, &options),
+            Err(nom::Err::Failure(ClsError::SizeMismatch {
+                section: "ColorSegment",
+                expected: 9,
+                found: 8
+            }))
+        ));
+        cs_bytes[0] -= 1;
+
+        // unusual color name flag
+        cs_bytes[8] = 2;
+        let (_, de_cs) = ColorSegment::try_from_bytes(&cs_bytes).unwrap();
+        assert_eq!(de_cs, cs);
+        let options = ParseOptions {
+            strict_name_flag: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ColorSegment::try_from_bytes_with(&cs_bytes, &options),
+            Err(nom::Err::Failure(ClsError::InvalidNameFlag(2)))
+        ));
+    }
 }
@@ -3,48 +3,105 @@
 //! Cls Color
 //!
 //! # Note
-//! Color structs with transparency of True are always converted to [0x0,0x0,0x0,0xFF] when converted to bytes.
+//! Color structs with transparency of True are always converted to [0x0,0x0,0x0,0x0] when converted to bytes.
 //! This is to match the actual transparency color created in ClipStudioPaint.
 //!
-//! In this case, if you set an arbitrary color and turn on transparency, the color will be transparent with color information in the color palette.
-//! This color will be rendered as transparent, but such a color cannot be created in the regular way.
+//! The RGB of a transparent color is therefore not kept, and a transparent color read from bytes has the RGB (0, 0, 0).
+//!
+//! # Alpha
+//! The alpha byte is kept as read and written back as is, and a color is transparent if its alpha is 0x00.
+//! ClipStudioPaint itself only writes 0x00(transparent) and 0xFF(opaque),
+//! and it is unknown how it renders other alpha values.
+
+mod contrast;
+mod css_function;
+mod css_name;
+mod hsv;
+mod lab;
+mod lerp;
+mod temperature;
+
+pub use temperature::ColorTemperature;
 
 use crate::colorset::common;
+use crate::error::ClsError;
+use alloc::{format, string::String, vec::Vec};
 use bytes;
+use core::{error, fmt};
 use nom;
+use num_traits::Euclid;
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
 use serde::{
     self,
     ser::{SerializeSeq, SerializeStruct},
 };
-use std::{error, fmt};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
 pub enum SerializeMode {
     Struct,
     Seq,
     Hex,
     HexWithNumberSign,
+    /// `RRGGBBAA`, transparent colors are `00000000`.
+    HexWithAlpha,
+    /// `#RRGGBBAA`, transparent colors are `#00000000`.
+    HexWithAlphaAndNumberSign,
+}
+
+/// A channel of the color in the cls file.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+/// A metric of [`Color::distance_by`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DistanceMetric {
+    /// Measured on RGB of 0..=255.
+    Euclidean,
+    /// Measured on RGB of 0..=255.
+    Manhattan,
+    /// Euclidean distance in CIE L*a*b*, see [`Color::to_lab`].
+    Cie76,
 }
 
 /// Color
 ///
-/// RGB + Transparency
+/// RGB + Alpha
 #[derive(Debug, PartialEq, Clone)]
 pub struct Color {
     red: u8,
     green: u8,
     blue: u8,
-    transparency: bool,
+    alpha: u8,
     serialize_mode: SerializeMode,
 }
 
+/// Alpha of transparency
+fn alpha_of(transparency: bool) -> u8 {
+    if transparency {
+        0x00
+    } else {
+        0xFF
+    }
+}
+
 impl Color {
     pub fn new(red: u8, green: u8, blue: u8, transparency: bool) -> Self {
+        Color::with_alpha(red, green, blue, alpha_of(transparency))
+    }
+
+    /// Create a Color with partial alpha. Alpha 0x00 is transparent.
+    pub fn with_alpha(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
         Color {
             red,
             green,
             blue,
-            transparency,
+            alpha,
             serialize_mode: SerializeMode::Struct,
         }
     }
@@ -54,13 +111,21 @@ impl Color {
         transparency: bool,
     ) -> Result<Self, ParseHexColorError> {
         let (red, green, blue) = parse_hex_color(hex_color)?;
-        Ok(Color {
-            red,
-            green,
-            blue,
-            transparency,
-            serialize_mode: SerializeMode::Struct,
-        })
+        Ok(Color::new(red, green, blue, transparency))
+    }
+
+    /// Create a Color from normalized float RGB.
+    ///
+    /// Each channel is clamped to 0.0..=1.0 and rounded to the nearest u8. NaN is treated as 0.0.
+    pub fn from_rgb_f32(red: f32, green: f32, blue: f32, transparency: bool) -> Self {
+        let to_u8 = |channel: f32| {
+            if channel.is_nan() {
+                0
+            } else {
+                (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+            }
+        };
+        Color::new(to_u8(red), to_u8(green), to_u8(blue), transparency)
     }
 
     pub fn set_rgb(&mut self, red: u8, green: u8, blue: u8) {
@@ -82,6 +147,52 @@ impl Color {
         (self.red, self.green, self.blue)
     }
 
+    /// Returns RGB normalized to 0.0..=1.0.
+    pub fn to_rgb_f32(&self) -> (f32, f32, f32) {
+        (
+            self.red as f32 / 255.0,
+            self.green as f32 / 255.0,
+            self.blue as f32 / 255.0,
+        )
+    }
+
+    /// Returns hue in degrees(0.0..360.0), or None for achromatic colors.
+    pub(crate) fn hue(&self) -> Option<f32> {
+        let (red, green, blue) = self.to_rgb_f32();
+        let max = red.max(green).max(blue);
+        let delta = max - red.min(green).min(blue);
+        if delta == 0.0 {
+            return None;
+        }
+
+        let hue = if max == red {
+            60.0 * ((green - blue) / delta)
+        } else if max == green {
+            60.0 * ((blue - red) / delta + 2.0)
+        } else {
+            60.0 * ((red - green) / delta + 4.0)
+        };
+        Some(Euclid::rem_euclid(&hue, &360.0))
+    }
+
+    /// Returns `RRGGBBAA` as written to the cls file, so transparent colors are `00000000`.
+    pub fn get_hex_color_with_alpha(&self, number_sign: bool) -> String {
+        let (red, green, blue, alpha) = (
+            self.get_channel(Channel::Red),
+            self.get_channel(Channel::Green),
+            self.get_channel(Channel::Blue),
+            self.get_channel(Channel::Alpha),
+        );
+        format!(
+            "{}{:02X}{:02X}{:02X}{:02X}",
+            if number_sign { "#" } else { "" },
+            red,
+            green,
+            blue,
+            alpha
+        )
+    }
+
     pub fn get_hex_color(&self, number_sign: bool) -> String {
         let red_hex = format!("{:02X?}", self.red);
         let green_hex = format!("{:02X?}", self.green);
@@ -96,12 +207,128 @@ impl Color {
         .concat()
     }
 
+    /// Returns the value of the channel as written to the cls file.
+    ///
+    /// # Note
+    /// A transparent color is `[0x0,0x0,0x0,0x0]`.
+    pub fn get_channel(&self, channel: Channel) -> u8 {
+        let (red, green, blue, alpha) = if self.get_transparency() {
+            (0, 0, 0, 0)
+        } else {
+            (self.red, self.green, self.blue, self.alpha)
+        };
+
+        match channel {
+            Channel::Red => red,
+            Channel::Green => green,
+            Channel::Blue => blue,
+            Channel::Alpha => alpha,
+        }
+    }
+
+    /// Returns the distance between two colors by `metric`.
+    ///
+    /// # Note
+    /// Transparent colors are 0.0 apart from each other, and infinitely apart from opaque colors.
+    pub fn distance_by(&self, other: &Color, metric: DistanceMetric) -> f32 {
+        match (self.get_transparency(), other.get_transparency()) {
+            (true, true) => return 0.0,
+            (true, false) | (false, true) => return f32::INFINITY,
+            (false, false) => {}
+        }
+
+        let diffs = if metric == DistanceMetric::Cie76 {
+            let ((l, a, b), (other_l, other_a, other_b)) = (self.to_lab(), other.to_lab());
+            [l - other_l, a - other_a, b - other_b]
+        } else {
+            [
+                self.red as f32 - other.red as f32,
+                self.green as f32 - other.green as f32,
+                self.blue as f32 - other.blue as f32,
+            ]
+        };
+        match metric {
+            DistanceMetric::Euclidean | DistanceMetric::Cie76 => {
+                diffs.iter().map(|diff| diff * diff).sum::<f32>().sqrt()
+            }
+            DistanceMetric::Manhattan => diffs.iter().map(|diff| diff.abs()).sum(),
+        }
+    }
+
+    /// Returns the perceptual distance between two colors, i.e. [`DistanceMetric::Cie76`].
+    pub fn distance(&self, other: &Color) -> f32 {
+        self.distance_by(other, DistanceMetric::Cie76)
+    }
+
+    /// Returns true if both colors are written as the same bytes in the cls file.
+    ///
+    /// Unlike `==`, the RGB of transparent colors and the serialize mode are ignored.
+    pub fn cls_eq(&self, other: &Color) -> bool {
+        use Channel::*;
+        [Red, Green, Blue, Alpha]
+            .into_iter()
+            .all(|channel| self.get_channel(channel) == other.get_channel(channel))
+    }
+
+    /// Returns true if both colors render the same.
+    ///
+    /// Any two transparent colors are equal regardless of their stored RGB, and the serialize mode is ignored.
+    /// Since the cls file stores transparent colors as 0x00000000, this currently agrees with [`Color::cls_eq`].
+    pub fn visually_eq(&self, other: &Color) -> bool {
+        if self.get_transparency() || other.get_transparency() {
+            return self.get_transparency() == other.get_transparency();
+        }
+        (self.red, self.green, self.blue, self.alpha)
+            == (other.red, other.green, other.blue, other.alpha)
+    }
+
+    /// Set transparency
+    ///
+    /// # Note
+    /// The alpha becomes 0x00 or 0xFF, so partial alpha is discarded.
     pub fn set_transparency(&mut self, transparency: bool) {
-        self.transparency = transparency
+        self.alpha = alpha_of(transparency);
     }
 
+    /// Returns a copy with the given transparency, see [`Color::set_transparency`].
+    pub fn with_transparency(mut self, transparency: bool) -> Self {
+        self.set_transparency(transparency);
+        self
+    }
+
+    /// Returns a copy with the given RGB, see [`Color::set_rgb`].
+    pub fn with_rgb(mut self, red: u8, green: u8, blue: u8) -> Self {
+        self.set_rgb(red, green, blue);
+        self
+    }
+
+    /// Returns true if the alpha is 0x00.
     pub fn get_transparency(&self) -> bool {
-        self.transparency
+        self.alpha == 0
+    }
+
+    pub fn set_alpha(&mut self, alpha: u8) {
+        self.alpha = alpha;
+    }
+
+    pub fn get_alpha(&self) -> u8 {
+        self.alpha
+    }
+
+    /// Returns the alpha byte, which is now always kept, see [`Color::get_alpha`].
+    #[deprecated(note = "the alpha byte is always kept, use `Color::get_alpha`")]
+    pub fn get_raw_alpha(&self) -> Option<u8> {
+        Some(self.alpha)
+    }
+
+    /// Parse a color keeping the alpha byte as read, the same as [`TryFromBytes::try_from_bytes`](common::TryFromBytes::try_from_bytes).
+    #[deprecated(note = "the alpha byte is always kept, use `TryFromBytes::try_from_bytes`")]
+    pub fn try_from_bytes_capturing_alpha(input: &[u8]) -> nom::IResult<&[u8], Self, ClsError> {
+        common::TryFromBytes::try_from_bytes(input)
+    }
+
+    pub fn set_serialize_mode(&mut self, serialize_mode: SerializeMode) {
+        self.serialize_mode = serialize_mode;
     }
 
     pub fn set_serialize_mode_struct(&mut self) {
@@ -120,6 +347,14 @@ impl Color {
         self.serialize_mode = SerializeMode::HexWithNumberSign;
     }
 
+    pub fn set_serialize_mode_hex_with_alpha(&mut self) {
+        self.serialize_mode = SerializeMode::HexWithAlpha;
+    }
+
+    pub fn set_serialize_mode_hex_with_alpha_and_number_sign(&mut self) {
+        self.serialize_mode = SerializeMode::HexWithAlphaAndNumberSign;
+    }
+
     pub fn get_serialize_mode(&self) -> &SerializeMode {
         &self.serialize_mode
     }
@@ -134,36 +369,44 @@ impl common::ClsSize for Color {
 // Color into Cls bytes.
 impl common::ExtendBytesMut for Color {
     fn extend_bytes(&self, extended: &mut bytes::BytesMut) {
-        if self.transparency {
+        if self.get_transparency() {
             extended.extend_from_slice(&[0, 0, 0, 0]);
         } else {
-            extended.extend_from_slice(&[self.red, self.green, self.blue, 0xFF]);
+            extended.extend_from_slice(&[self.red, self.green, self.blue, self.alpha]);
         }
     }
 }
 
 // Color try from Bytes.
 impl common::TryFromBytes for Color {
-    fn try_from_bytes(input: &[u8]) -> nom::IResult<&[u8], Self>
+    fn try_from_bytes_with<'a>(
+        input: &'a [u8],
+        _options: &common::ParseOptions,
+    ) -> nom::IResult<&'a [u8], Self, ClsError>
     where
         Self: Sized,
     {
         use nom::number::complete::le_u8;
 
-        let (input, (red, green, blue, tp)) =
-            nom::sequence::tuple((le_u8, le_u8, le_u8, le_u8))(input)?;
+        let (input, (red, green, blue, alpha)) =
+            nom::sequence::tuple((le_u8, le_u8, le_u8, le_u8))(input)
+                .map_err(common::unexpected_eof("Color", 4))?;
 
-        if tp == 0 {
+        if alpha == 0 {
             Ok((input, Color::new(0, 0, 0, true)))
         } else {
-            Ok((input, Color::new(red, green, blue, false)))
+            Ok((input, Color::with_alpha(red, green, blue, alpha)))
         }
     }
 }
 
-fn parse_hex_color(hex_color: &str) -> Result<(u8, u8, u8), ParseHexColorError> {
+pub(crate) fn parse_hex_color(hex_color: &str) -> Result<(u8, u8, u8), ParseHexColorError> {
     // #FFFFFF , FFFFFF, #FFF, FFF　is valid
     let mut hex_color = hex_color;
+    // Slicing below assumes one byte per char
+    if !hex_color.is_ascii() {
+        return Err(ParseHexColorError::InvalidHexColorStrError);
+    }
 
     let hex_color_len = hex_color.len();
     // Check Number sign(#)
@@ -211,7 +454,7 @@ impl serde::Serialize for Color {
     {
         match self.serialize_mode {
             SerializeMode::Seq => {
-                if self.transparency {
+                if self.get_transparency() {
                     let seq = serializer.serialize_seq(None)?;
                     seq.end()
                 } else {
@@ -223,43 +466,175 @@ impl serde::Serialize for Color {
                 }
             }
             SerializeMode::Hex => {
-                if self.transparency {
+                if self.get_transparency() {
                     serializer.serialize_str("")
                 } else {
                     serializer.serialize_str(&self.get_hex_color(false))
                 }
             }
             SerializeMode::HexWithNumberSign => {
-                if self.transparency {
+                if self.get_transparency() {
                     serializer.serialize_str("")
                 } else {
                     serializer.serialize_str(&self.get_hex_color(true))
                 }
             }
+            SerializeMode::HexWithAlpha => {
+                serializer.serialize_str(&self.get_hex_color_with_alpha(false))
+            }
+            SerializeMode::HexWithAlphaAndNumberSign => {
+                serializer.serialize_str(&self.get_hex_color_with_alpha(true))
+            }
             SerializeMode::Struct => {
-                let mut color = serializer.serialize_struct("Color", 4)?;
+                let partial_alpha = self.alpha != alpha_of(self.get_transparency());
+                let mut color =
+                    serializer.serialize_struct("Color", if partial_alpha { 5 } else { 4 })?;
                 color.serialize_field("red", &self.red)?;
                 color.serialize_field("green", &self.green)?;
                 color.serialize_field("blue", &self.blue)?;
-                color.serialize_field("transparency", &self.transparency)?;
+                color.serialize_field("transparency", &self.get_transparency())?;
+                if partial_alpha {
+                    color.serialize_field("alpha", &self.alpha)?;
+                }
                 color.end()
             }
         }
     }
 }
 
+/// Accepts all forms of [`SerializeMode`], and the serialize mode is set to the form.
+///
+/// # Note
+/// Transparent colors are `[]` in the seq form, `""` in the hex forms and alpha 0 in the hex forms with alpha.
+/// `""` is deserialized with [`SerializeMode::Hex`].
+/// 8 digits hex is deserialized with [`SerializeMode::HexWithAlpha`] or [`SerializeMode::HexWithAlphaAndNumberSign`].
+/// Only the struct form carries partial alpha, as the optional field `alpha`.
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Struct {
+                red: u8,
+                green: u8,
+                blue: u8,
+                transparency: bool,
+                alpha: Option<u8>,
+            },
+            Seq(Vec<u8>),
+            Hex(String),
+        }
+
+        let color = match Repr::deserialize(deserializer)? {
+            Repr::Struct {
+                red,
+                green,
+                blue,
+                transparency,
+                alpha,
+            } => match alpha {
+                Some(alpha) if !transparency => Color::with_alpha(red, green, blue, alpha),
+                _ => Color::new(red, green, blue, transparency),
+            },
+            Repr::Seq(seq) => {
+                let mut color = match seq.as_slice() {
+                    [] => Color::new(0, 0, 0, true),
+                    [red, green, blue] => Color::new(*red, *green, *blue, false),
+                    _ => return Err(D::Error::invalid_length(seq.len(), &"0 or 3 channels")),
+                };
+                color.set_serialize_mode_seq();
+                color
+            }
+            Repr::Hex(hex_color) => {
+                let number_sign = hex_color.starts_with('#');
+                let digits = hex_color.strip_prefix('#').unwrap_or(&hex_color);
+                let mut color = if hex_color.is_empty() {
+                    Color::new(0, 0, 0, true)
+                } else if digits.len() == 8 && digits.is_ascii() {
+                    let (red, green, blue) =
+                        parse_hex_color(&digits[..6]).map_err(D::Error::custom)?;
+                    let alpha = u8::from_str_radix(&digits[6..], 16)
+                        .map_err(|err| D::Error::custom(ParseHexColorError::ParseIntError(err)))?;
+                    Color::with_alpha(red, green, blue, alpha)
+                } else {
+                    Color::new_with_hex_color(&hex_color, false).map_err(D::Error::custom)?
+                };
+                match (digits.len() == 8, number_sign) {
+                    (true, true) => color.set_serialize_mode_hex_with_alpha_and_number_sign(),
+                    (true, false) => color.set_serialize_mode_hex_with_alpha(),
+                    (false, true) => color.set_serialize_mode_hex_with_number_sign(),
+                    (false, false) => color.set_serialize_mode_hex(),
+                }
+                color
+            }
+        };
+        Ok(color)
+    }
+}
+
+/// Parse `#RGB`, `#RRGGBB`(`#` is optional), `rgb()`/`rgba()` or `transparent`.
+///
+/// # Note
+/// `transparent` is black with transparency, like the default "Color0".
+impl core::str::FromStr for Color {
+    type Err = ParseHexColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("transparent") {
+            Ok(Color::new(0, 0, 0, true))
+        } else if s
+            .get(..3)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case("rgb"))
+        {
+            Color::from_css_function(s).map_err(|_| ParseHexColorError::UnrecognizedFormat)
+        } else if s
+            .strip_prefix('#')
+            .unwrap_or(s)
+            .chars()
+            .all(|c| c.is_ascii_hexdigit())
+        {
+            Color::new_with_hex_color(s, false)
+        } else {
+            Err(ParseHexColorError::UnrecognizedFormat)
+        }
+    }
+}
+
+/// `#RRGGBB`, or `transparent` for transparent colors. It can be parsed back by `FromStr`.
+///
+/// # Note
+/// Like [`SerializeMode::HexWithNumberSign`], a partial alpha is not shown.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.get_transparency() {
+            write!(f, "transparent")
+        } else {
+            write!(f, "{}", self.get_hex_color(true))
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ParseHexColorError {
     InvalidHexColorStrError,
-    ParseIntError(std::num::ParseIntError),
+    ParseIntError(core::num::ParseIntError),
+    /// Neither hex, `rgb()` nor `transparent`, see [`Color`]'s `FromStr`.
+    UnrecognizedFormat,
 }
 
 impl fmt::Display for ParseHexColorError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use ParseHexColorError::*;
         match self {
             InvalidHexColorStrError => write!(f, "{}", "Invalid Hex Color Str."),
             ParseIntError(parse_int_error) => write!(f, "{}", parse_int_error),
+            UnrecognizedFormat => write!(f, "Unrecognized color format."),
         }
     }
 }
@@ -271,7 +646,7 @@ mod tests {
 
     use super::bytes;
     use super::common::{ExtendBytesMut, TryFromBytes};
-    use super::Color;
+    use super::{Color, SerializeMode};
     mod setup {
         use super::*;
         pub fn color_setup(transparency: bool) -> Color {
@@ -303,6 +678,10 @@ mod tests {
 
         let (_, de_tp_clr) = Color::try_from_bytes(tp_bytes.as_ref()).unwrap();
         assert_ne!(de_tp_clr, tp_clr);
+        assert!(de_tp_clr.visually_eq(&tp_clr));
+        assert!(!de_tp_clr.visually_eq(&color_setup(false)));
+        assert!(color_setup(false).visually_eq(&Color::new(1, 128, 255, false)));
+        assert!(!Color::with_alpha(1, 128, 255, 0x80).visually_eq(&color_setup(false)));
 
         // change to expected val
         tp_clr.set_rgb(0, 0, 0);
@@ -342,7 +721,9 @@ mod tests {
             )
         }
 
-        let invalid_str_arr = ["#FFEEGG", "AFFEE00", "#FE*", "#", "FFEE00#"];
+        let invalid_str_arr = [
+            "#FFEEGG", "AFFEE00", "#FE*", "#", "FFEE00#", "", "#FF", "é12", "#FFé1",
+        ];
 
         for invs in invalid_str_arr {
             assert!(
@@ -353,6 +734,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_str_test() {
+        use super::ParseHexColorError;
+
+        let orange = Color::new(255, 128, 0, false);
+        assert_eq!("#FF8000".parse::<Color>().unwrap().get_rgb(), (255, 128, 0));
+        assert_eq!("ff8000".parse::<Color>().unwrap().get_rgb(), (255, 128, 0));
+        assert_eq!("#F80".parse::<Color>().unwrap().get_rgb(), (255, 136, 0));
+        assert_eq!(" rgb(255, 128, 0) ".parse::<Color>().unwrap(), orange);
+        assert_eq!("RGB(255 128 0)".parse::<Color>().unwrap(), orange);
+
+        let transparent = "Transparent".parse::<Color>().unwrap();
+        assert!(transparent.get_transparency());
+        assert_eq!(transparent.get_rgb(), (0, 0, 0));
+
+        assert!(matches!(
+            "#FF80".parse::<Color>(),
+            Err(ParseHexColorError::InvalidHexColorStrError)
+        ));
+        for invalid in ["tomato", "rgb(1, 2)", "#GG0000", "hsl(0, 0%, 0%)"] {
+            assert!(
+                matches!(
+                    invalid.parse::<Color>(),
+                    Err(ParseHexColorError::UnrecognizedFormat)
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn display_test() {
+        let orange = Color::new(255, 128, 0, false);
+        assert_eq!(orange.to_string(), "#FF8000");
+        assert_eq!(orange.to_string().parse::<Color>().unwrap(), orange);
+        assert_eq!(Color::new(1, 2, 3, true).to_string(), "transparent");
+        assert_eq!(Color::with_alpha(1, 2, 3, 0x80).to_string(), "#010203");
+    }
+
     #[test]
     fn serialize_test() {
         use serde_json;
@@ -394,5 +815,166 @@ mod tests {
         test_color.set_transparency(true);
         let tc_hex_with_ns_tp_json = serde_json::to_string(&test_color).unwrap();
         assert_eq!(tc_hex_with_ns_tp_json, "\"\"");
+
+        // hex with alpha
+        let mut test_color = Color::with_alpha(255, 128, 0, 0x80);
+        test_color.set_serialize_mode_hex_with_alpha();
+        assert_eq!(serde_json::to_string(&test_color).unwrap(), "\"FF800080\"");
+        test_color.set_serialize_mode_hex_with_alpha_and_number_sign();
+        assert_eq!(serde_json::to_string(&test_color).unwrap(), "\"#FF800080\"");
+        test_color.set_alpha(0xFF);
+        assert_eq!(serde_json::to_string(&test_color).unwrap(), "\"#FF8000FF\"");
+        // transparency on
+        test_color.set_transparency(true);
+        assert_eq!(serde_json::to_string(&test_color).unwrap(), "\"#00000000\"");
+    }
+
+    #[test]
+    fn truncated_test() {
+        use crate::error::ClsError;
+
+        let err = Color::try_from_bytes(&[1, 128, 255]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Error(ClsError::UnexpectedEof {
+                expected: 4,
+                section: "Color"
+            })
+        ));
+    }
+
+    #[test]
+    fn alpha_test() {
+        let (_, half_clr) = Color::try_from_bytes(&[1, 128, 255, 0x80]).unwrap();
+        assert_eq!(half_clr, Color::with_alpha(1, 128, 255, 0x80));
+        assert_eq!(half_clr.get_alpha(), 0x80);
+        assert!(!half_clr.get_transparency());
+
+        let mut half_bytes = bytes::BytesMut::new();
+        half_clr.extend_bytes(&mut half_bytes);
+        assert_eq!(half_bytes.as_ref(), [1, 128, 255, 0x80]);
+
+        // transparency is a shorthand of alpha
+        assert_eq!(color_setup(true).get_alpha(), 0x00);
+        assert_eq!(color_setup(false).get_alpha(), 0xFF);
+        let mut tp_clr = half_clr.clone();
+        tp_clr.set_alpha(0);
+        assert!(tp_clr.get_transparency());
+        tp_clr.set_transparency(false);
+        assert_eq!(tp_clr.get_alpha(), 0xFF);
+
+        // partial alpha in the struct form
+        let json = serde_json::to_string(&half_clr).unwrap();
+        assert_eq!(
+            json,
+            "{\"red\":1,\"green\":128,\"blue\":255,\"transparency\":false,\"alpha\":128}"
+        );
+        assert_eq!(serde_json::from_str::<Color>(&json).unwrap(), half_clr);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn capture_alpha_compat_test() {
+        use crate::colorset::common::ParseOptions;
+
+        let (_, clr) = Color::try_from_bytes_capturing_alpha(&[1, 128, 255, 0x80]).unwrap();
+        assert_eq!(clr, Color::with_alpha(1, 128, 255, 0x80));
+        assert_eq!(clr.get_raw_alpha(), Some(0x80));
+
+        let options = ParseOptions {
+            capture_alpha: true,
+            ..Default::default()
+        };
+        let (_, opt_clr) = Color::try_from_bytes_with(&[1, 128, 255, 0x80], &options).unwrap();
+        assert_eq!(opt_clr, clr);
+    }
+
+    #[test]
+    fn rgb_f32_test() {
+        let clr = Color::new(0, 128, 255, false);
+        assert_eq!(clr.to_rgb_f32(), (0.0, 128.0 / 255.0, 1.0));
+
+        let from_f32 = Color::from_rgb_f32(0.0, 128.0 / 255.0, 1.0, false);
+        assert_eq!(from_f32, clr);
+
+        // clamping
+        let clamped = Color::from_rgb_f32(-0.5, f32::NAN, 1.5, true);
+        assert_eq!(clamped.get_rgb(), (0, 0, 255));
+        assert!(clamped.get_transparency());
+
+        // round trip
+        for channel in 0..=255u8 {
+            let clr = Color::new(channel, channel, channel, false);
+            let (red, green, blue) = clr.to_rgb_f32();
+            assert_eq!(Color::from_rgb_f32(red, green, blue, false), clr);
+        }
+    }
+
+    #[test]
+    fn with_test() {
+        let mut clr = Color::new_with_hex_color("#FF8000", false).unwrap();
+        clr.set_serialize_mode_hex();
+
+        let tp_clr = clr.clone().with_transparency(true);
+        assert!(tp_clr.get_transparency());
+        assert_eq!(tp_clr.get_rgb(), (255, 128, 0));
+        assert_eq!(tp_clr.get_serialize_mode(), &SerializeMode::Hex);
+        assert!(!clr.get_transparency());
+
+        let blue = clr.with_rgb(0, 0, 255).with_transparency(false);
+        assert_eq!(blue.get_hex_color(true), "#0000FF");
+        assert_eq!(blue.get_serialize_mode(), &SerializeMode::Hex);
+    }
+
+    #[test]
+    fn distance_by_test() {
+        use super::DistanceMetric::*;
+
+        let black = Color::new(0, 0, 0, false);
+        let clr = Color::new(3, 4, 0, false);
+        assert_eq!(black.distance_by(&clr, Euclidean), 5.0);
+        assert_eq!(black.distance_by(&clr, Manhattan), 7.0);
+
+        let tp_clr = Color::new(1, 2, 3, true);
+        assert_eq!(tp_clr.distance_by(&color_setup(true), Euclidean), 0.0);
+        assert_eq!(tp_clr.distance_by(&black, Manhattan), f32::INFINITY);
+
+        let white = Color::new(255, 255, 255, false);
+        assert!((black.distance(&white) - 100.0).abs() < 1e-3);
+        assert_eq!(black.distance_by(&white, Cie76), black.distance(&white));
+        assert_eq!(tp_clr.distance(&black), f32::INFINITY);
+    }
+
+    #[test]
+    fn deserialize_test() {
+        let clr: Color =
+            serde_json::from_str("{\"red\":1,\"green\":128,\"blue\":255,\"transparency\":false}")
+                .unwrap();
+        assert_eq!(clr, color_setup(false));
+
+        for (json, mode) in [
+            ("[1,128,255]", SerializeMode::Seq),
+            ("\"0180FF\"", SerializeMode::Hex),
+            ("\"#0180FF\"", SerializeMode::HexWithNumberSign),
+            ("\"0180FFFF\"", SerializeMode::HexWithAlpha),
+            ("\"#0180FFFF\"", SerializeMode::HexWithAlphaAndNumberSign),
+        ] {
+            let clr: Color = serde_json::from_str(json).unwrap();
+            assert_eq!(clr.get_rgb(), (1, 128, 255));
+            assert_eq!(clr.get_serialize_mode(), &mode);
+            assert_eq!(serde_json::to_string(&clr).unwrap(), json);
+        }
+
+        let clr: Color = serde_json::from_str("\"#0180FF80\"").unwrap();
+        assert_eq!(clr.get_alpha(), 0x80);
+
+        for json in ["[]", "\"\"", "\"#00000000\""] {
+            let clr: Color = serde_json::from_str(json).unwrap();
+            assert!(clr.get_transparency());
+        }
+
+        for json in ["[1,2]", "\"#GGGGGG\"", "{\"red\":1}"] {
+            assert!(serde_json::from_str::<Color>(json).is_err());
+        }
     }
 }
@@ -0,0 +1,39 @@
+//! Contrast
+//!
+//! Relative luminance and contrast ratio as defined in WCAG 2.x.
+//!
+//! # Note
+//! The transparency is ignored, the RGB is used as is.
+
+use super::{lab::linearize, Color};
+
+impl Color {
+    /// Returns the relative luminance(0.0..=1.0).
+    pub fn relative_luminance(&self) -> f32 {
+        let (red, green, blue) = self.to_rgb_f32();
+        0.2126 * linearize(red) + 0.7152 * linearize(green) + 0.0722 * linearize(blue)
+    }
+
+    /// Returns the contrast ratio(1.0..=21.0) between two colors.
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let (lum, other_lum) = (self.relative_luminance(), other.relative_luminance());
+        (lum.max(other_lum) + 0.05) / (lum.min(other_lum) + 0.05)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    #[test]
+    fn contrast_ratio_test() {
+        let black = Color::new(0, 0, 0, false);
+        let white = Color::new(255, 255, 255, false);
+
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 1e-4);
+        assert_eq!(white.contrast_ratio(&black), black.contrast_ratio(&white));
+        assert_eq!(white.contrast_ratio(&white), 1.0);
+        // #767676 is the darkest gray passing AA(4.5) on white.
+        assert!(Color::new(0x76, 0x76, 0x76, false).contrast_ratio(&white) >= 4.5);
+    }
+}
@@ -0,0 +1,96 @@
+//! CSS Color Functions
+//!
+//! `rgb()` and `rgba()` of CSS Color Module Level 4.
+//!
+//! # Note
+//! - Both comma separated `rgb(255, 128, 0)` and space separated `rgb(255 128 0 / 0)` are accepted.
+//! - Channels are numbers(0..=255) or percentages, and are clamped and rounded to u8.
+//! - Alpha(0.0..=1.0 or a percentage) is scaled to 0..=255, and alpha 0 is transparent.
+
+use super::Color;
+use crate::error::ClsError;
+use alloc::{format, vec::Vec};
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
+
+/// Parse a channel of a number or a percentage into 0.0..=`max`.
+fn parse_value(val: &str, max: f32) -> Option<f32> {
+    let num = match val.strip_suffix('%') {
+        Some(percentage) => percentage.parse::<f32>().ok()? / 100.0 * max,
+        None => val.parse::<f32>().ok()?,
+    };
+    (!num.is_nan()).then(|| num.clamp(0.0, max))
+}
+
+impl Color {
+    /// Parse CSS `rgb()` or `rgba()` function syntax.
+    pub fn from_css_function(css: &str) -> Result<Color, ClsError> {
+        let invalid = || ClsError::InvalidFormat(format!("CSS color function \"{}\"", css));
+
+        let css = css.trim();
+        let lower = css.to_ascii_lowercase();
+        let args = lower
+            .strip_prefix("rgba")
+            .or_else(|| lower.strip_prefix("rgb"))
+            .and_then(|rest| rest.trim_start().strip_prefix('('))
+            .and_then(|rest| rest.strip_suffix(')'))
+            .ok_or_else(invalid)?;
+
+        let args = args
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .collect::<Vec<_>>();
+        if args.len() != 3 && args.len() != 4 {
+            return Err(invalid());
+        }
+
+        let mut rgb = [0u8; 3];
+        for (channel, arg) in rgb.iter_mut().zip(args.iter()) {
+            *channel = parse_value(arg, 255.0).ok_or_else(invalid)?.round() as u8;
+        }
+        let alpha = match args.get(3) {
+            Some(alpha) => (parse_value(alpha, 1.0).ok_or_else(invalid)? * 255.0).round() as u8,
+            None => 0xFF,
+        };
+
+        Ok(Color::with_alpha(rgb[0], rgb[1], rgb[2], alpha))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+    use crate::error::ClsError;
+
+    #[test]
+    fn css_function_test() {
+        let clr = Color::from_css_function(" rgb(255, 128, 0) ").unwrap();
+        assert_eq!(clr, Color::new(255, 128, 0, false));
+
+        let clr = Color::from_css_function("RGBA(255, 128, 0, 0)").unwrap();
+        assert!(clr.get_transparency());
+        assert_eq!(clr.get_rgb(), (255, 128, 0));
+
+        let clr = Color::from_css_function("rgba(100%, 50%, 0%, 0.5)").unwrap();
+        assert_eq!(clr, Color::with_alpha(255, 128, 0, 128));
+
+        let clr = Color::from_css_function("rgb(255 128 0 / 0%)").unwrap();
+        assert!(clr.get_transparency());
+    }
+
+    #[test]
+    fn css_function_invalid_test() {
+        for css in [
+            "rgb(255, 128)",
+            "rgb(255, 128, 0",
+            "hsl(0, 100%, 50%)",
+            "rgb(red, 128, 0)",
+            "rgba(255, 128, 0, 0, 0)",
+        ] {
+            assert!(matches!(
+                Color::from_css_function(css),
+                Err(ClsError::InvalidFormat(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! HSV / HSL
+//!
+//! Hue in degrees(0.0..360.0), and the others in 0.0..=1.0.
+//!
+//! # Note
+//! - Achromatic colors have hue of 0.0.
+//! - On set, hue wraps around 360.0, the others are clamped to 0.0..=1.0, and NaN is treated as 0.0.
+//! - Setters only change the RGB, the alpha is kept.
+
+use super::Color;
+use num_traits::Euclid;
+
+/// Wrap hue into 0.0..360.0.
+fn wrap_hue(hue: f32) -> f32 {
+    if hue.is_nan() {
+        0.0
+    } else {
+        // rem_euclid can round up to 360.0 for tiny negative values.
+        let hue = Euclid::rem_euclid(&hue, &360.0);
+        if hue >= 360.0 {
+            0.0
+        } else {
+            hue
+        }
+    }
+}
+
+/// Clamp into 0.0..=1.0, treating NaN as 0.0.
+pub(super) fn clamp_unit(val: f32) -> f32 {
+    if val.is_nan() {
+        0.0
+    } else {
+        val.clamp(0.0, 1.0)
+    }
+}
+
+/// Returns normalized RGB of `hue` with chroma and the lightness offset.
+fn rgb_from_chroma(hue: f32, chroma: f32, offset: f32) -> (f32, f32, f32) {
+    let section = hue / 60.0;
+    let x = chroma * (1.0 - (section % 2.0 - 1.0).abs());
+    let (red, green, blue) = match section as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    (red + offset, green + offset, blue + offset)
+}
+
+impl Color {
+    /// Returns (hue, saturation, value).
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (red, green, blue) = self.to_rgb_f32();
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (self.hue().unwrap_or(0.0), saturation, max)
+    }
+
+    /// Returns (hue, saturation, lightness).
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (red, green, blue) = self.to_rgb_f32();
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (self.hue().unwrap_or(0.0), saturation, lightness)
+    }
+
+    pub fn set_hsv(&mut self, hue: f32, saturation: f32, value: f32) {
+        let (saturation, value) = (clamp_unit(saturation), clamp_unit(value));
+        let chroma = value * saturation;
+        let (red, green, blue) = rgb_from_chroma(wrap_hue(hue), chroma, value - chroma);
+        self.set_rgb_f32(red, green, blue);
+    }
+
+    pub fn set_hsl(&mut self, hue: f32, saturation: f32, lightness: f32) {
+        let (saturation, lightness) = (clamp_unit(saturation), clamp_unit(lightness));
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let (red, green, blue) = rgb_from_chroma(wrap_hue(hue), chroma, lightness - chroma / 2.0);
+        self.set_rgb_f32(red, green, blue);
+    }
+
+    /// Set RGB normalized to 0.0..=1.0, see [`Color::from_rgb_f32`].
+    fn set_rgb_f32(&mut self, red: f32, green: f32, blue: f32) {
+        let (red, green, blue) = Color::from_rgb_f32(red, green, blue, false).get_rgb();
+        self.set_rgb(red, green, blue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    fn assert_close((a0, a1, a2): (f32, f32, f32), (b0, b1, b2): (f32, f32, f32)) {
+        for (a, b) in [(a0, b0), (a1, b1), (a2, b2)] {
+            assert!(
+                (a - b).abs() < 1e-3,
+                "{:?} != {:?}",
+                (a0, a1, a2),
+                (b0, b1, b2)
+            );
+        }
+    }
+
+    #[test]
+    fn hsv_hsl_test() {
+        let orange = Color::new(255, 128, 0, false);
+        assert_close(orange.to_hsv(), (30.1176, 1.0, 1.0));
+        assert_close(orange.to_hsl(), (30.1176, 1.0, 0.5));
+
+        // grays have no NaN
+        let gray = Color::new(128, 128, 128, false);
+        assert_close(gray.to_hsv(), (0.0, 0.0, 128.0 / 255.0));
+        assert_close(gray.to_hsl(), (0.0, 0.0, 128.0 / 255.0));
+        assert_close(Color::new(0, 0, 0, false).to_hsv(), (0.0, 0.0, 0.0));
+
+        let mut clr = Color::new(0, 0, 0, true);
+        clr.set_hsv(120.0, 1.0, 1.0);
+        assert_eq!(clr.get_rgb(), (0, 255, 0));
+        assert!(clr.get_transparency());
+
+        // wrap around and clamping
+        clr.set_hsv(-120.0, 2.0, 1.0);
+        assert_eq!(clr.get_rgb(), (0, 0, 255));
+        clr.set_hsl(480.0, 1.0, 0.5);
+        assert_eq!(clr.get_rgb(), (0, 255, 0));
+        clr.set_hsl(f32::NAN, -1.0, 1.5);
+        assert_eq!(clr.get_rgb(), (255, 255, 255));
+    }
+
+    #[test]
+    fn hsv_hsl_roundtrip_test() {
+        for red in (0..=255u8).step_by(15) {
+            for green in (0..=255u8).step_by(15) {
+                for blue in (0..=255u8).step_by(15) {
+                    let clr = Color::new(red, green, blue, false);
+
+                    let mut from_hsv = clr.clone();
+                    let (hue, saturation, value) = clr.to_hsv();
+                    from_hsv.set_hsv(hue, saturation, value);
+                    assert_eq!(from_hsv, clr);
+
+                    let mut from_hsl = clr.clone();
+                    let (hue, saturation, lightness) = clr.to_hsl();
+                    from_hsl.set_hsl(hue, saturation, lightness);
+                    assert_eq!(from_hsl, clr);
+                }
+            }
+        }
+    }
+}
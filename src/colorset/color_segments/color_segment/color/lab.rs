@@ -0,0 +1,79 @@
+//! CIE L*a*b*
+//!
+//! sRGB is converted through XYZ with the D65 white point.
+//!
+//! # Note
+//! - L* is in 0.0..=100.0, and a* and b* are roughly in -128.0..=128.0.
+//! - The alpha is ignored.
+
+use super::Color;
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
+
+/// D65 white point of XYZ, scaled to Y = 1.0.
+const WHITE_D65: (f32, f32, f32) = (0.95047, 1.0, 1.08883);
+
+/// Undo the sRGB gamma of a channel normalized to 0.0..=1.0.
+///
+/// # Note
+/// WCAG 2.x uses 0.03928 instead of 0.04045 as the threshold, but no 8bit channel is between them.
+pub(super) fn linearize(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn lab_f(t: f32) -> f32 {
+    const DELTA: f32 = 6.0 / 29.0;
+    if t > DELTA * DELTA * DELTA {
+        t.cbrt()
+    } else {
+        t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+    }
+}
+
+impl Color {
+    /// Returns (L*, a*, b*).
+    pub fn to_lab(&self) -> (f32, f32, f32) {
+        let (red, green, blue) = self.to_rgb_f32();
+        let (red, green, blue) = (linearize(red), linearize(green), linearize(blue));
+
+        let x = 0.4124 * red + 0.3576 * green + 0.1805 * blue;
+        let y = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+        let z = 0.0193 * red + 0.1192 * green + 0.9505 * blue;
+
+        let (fx, fy, fz) = (
+            lab_f(x / WHITE_D65.0),
+            lab_f(y / WHITE_D65.1),
+            lab_f(z / WHITE_D65.2),
+        );
+        (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    #[test]
+    fn to_lab_test() {
+        for (clr, expected) in [
+            (Color::new(0, 0, 0, false), (0.0, 0.0, 0.0)),
+            (Color::new(255, 255, 255, false), (100.0, 0.0, 0.0)),
+            (Color::new(255, 0, 0, false), (53.24, 80.09, 67.20)),
+            (Color::new(0, 0, 255, false), (32.30, 79.19, -107.86)),
+        ] {
+            let (l, a, b) = clr.to_lab();
+            assert!(
+                (l - expected.0).abs() < 0.05
+                    && (a - expected.1).abs() < 0.05
+                    && (b - expected.2).abs() < 0.05,
+                "{:?} != {:?}",
+                (l, a, b),
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Lerp
+//!
+//! Linear interpolation between two colors, e.g. for gradients.
+//!
+//! # Note
+//! - `t` is clamped to 0.0..=1.0, and NaN is treated as 0.0.
+//! - At `t` of 0.0 or 1.0 the endpoint is returned as is, so it keeps its transparency.
+//!   Otherwise the result is opaque.
+
+use super::{hsv::clamp_unit, lab::linearize, Color};
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
+
+/// Apply the sRGB gamma.
+fn delinearize(channel: f32) -> f32 {
+    if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Returns (L, a, b) of OKLab.
+fn to_oklab(color: &Color) -> (f32, f32, f32) {
+    let (red, green, blue) = color.to_rgb_f32();
+    let (red, green, blue) = (linearize(red), linearize(green), linearize(blue));
+
+    let l = (0.4122215 * red + 0.5363325 * green + 0.0514460 * blue).cbrt();
+    let m = (0.2119035 * red + 0.6806995 * green + 0.107397 * blue).cbrt();
+    let s = (0.0883025 * red + 0.2817188 * green + 0.6299787 * blue).cbrt();
+
+    (
+        0.2104543 * l + 0.7936178 * m - 0.0040720 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.0259040 * l + 0.7827718 * m - 0.8086758 * s,
+    )
+}
+
+/// Returns the opaque color of OKLab.
+fn from_oklab((l, a, b): (f32, f32, f32)) -> Color {
+    let l_ = (l + 0.3963378 * a + 0.2158038 * b).powi(3);
+    let m_ = (l - 0.1055613 * a - 0.0638542 * b).powi(3);
+    let s_ = (l - 0.0894842 * a - 1.2914856 * b).powi(3);
+
+    Color::from_rgb_f32(
+        delinearize(4.0767417 * l_ - 3.3077116 * m_ + 0.2309699 * s_),
+        delinearize(-1.268438 * l_ + 2.6097574 * m_ - 0.3413194 * s_),
+        delinearize(-0.0041961 * l_ - 0.7034186 * m_ + 1.7076147 * s_),
+        false,
+    )
+}
+
+impl Color {
+    /// Interpolate each channel of RGB in sRGB.
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        self.lerp_with(other, t, |t| {
+            let ((red, green, blue), (other_red, other_green, other_blue)) =
+                (self.to_rgb_f32(), other.to_rgb_f32());
+            Color::from_rgb_f32(
+                red + (other_red - red) * t,
+                green + (other_green - green) * t,
+                blue + (other_blue - blue) * t,
+                false,
+            )
+        })
+    }
+
+    /// Interpolate in OKLab, which gives perceptually smoother ramps than [`Color::lerp`].
+    pub fn lerp_oklab(&self, other: &Color, t: f32) -> Color {
+        self.lerp_with(other, t, |t| {
+            let ((l, a, b), (other_l, other_a, other_b)) = (to_oklab(self), to_oklab(other));
+            from_oklab((
+                l + (other_l - l) * t,
+                a + (other_a - a) * t,
+                b + (other_b - b) * t,
+            ))
+        })
+    }
+
+    fn lerp_with(&self, other: &Color, t: f32, lerp: impl FnOnce(f32) -> Color) -> Color {
+        let t = clamp_unit(t);
+        if t == 0.0 {
+            self.clone()
+        } else if t == 1.0 {
+            other.clone()
+        } else {
+            lerp(t)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    #[test]
+    fn lerp_test() {
+        let black = Color::new(0, 0, 0, false);
+        let white = Color::new(255, 255, 255, false);
+        assert_eq!(black.lerp(&white, 0.5).get_rgb(), (128, 128, 128));
+        assert_eq!(black.lerp(&white, -1.0), black);
+        assert_eq!(black.lerp(&white, 2.0), white);
+        assert_eq!(black.lerp(&white, f32::NAN), black);
+
+        // The midpoint of OKLab lightness is darker than the one of sRGB.
+        assert_eq!(black.lerp_oklab(&white, 0.5).get_rgb(), (99, 99, 99));
+        assert_eq!(black.lerp_oklab(&white, 1.0), white);
+
+        // transparency only at the transparent end
+        let tp_red = Color::new(255, 0, 0, true);
+        assert!(tp_red.lerp(&white, 0.0).get_transparency());
+        assert!(!tp_red.lerp(&white, 0.01).get_transparency());
+        assert!(!white.lerp_oklab(&tp_red, 0.99).get_transparency());
+        assert!(white.lerp_oklab(&tp_red, 1.0).get_transparency());
+    }
+
+    #[test]
+    fn oklab_roundtrip_test() {
+        for red in (0..=255u8).step_by(15) {
+            for green in (0..=255u8).step_by(15) {
+                for blue in (0..=255u8).step_by(15) {
+                    let clr = Color::new(red, green, blue, false);
+                    assert_eq!(super::from_oklab(super::to_oklab(&clr)), clr);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Color Temperature
+//!
+//! # Note
+//! Colors are classified by hue(HSV) as follows.
+//!     - Warm : 0 <= hue < 90 or 300 <= hue < 360 (red, orange, yellow and magenta)
+//!     - Cool : 90 <= hue < 300 (green, cyan, blue and violet)
+//!     - Neutral : transparent colors, and colors with saturation(HSV) below 0.1
+//! Saturation of near grays is unstable, so they are all neutral.
+
+use super::Color;
+
+/// Saturation below this is neutral.
+const NEUTRAL_SATURATION: f32 = 0.1;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ColorTemperature {
+    Warm,
+    Cool,
+    Neutral,
+}
+
+impl Color {
+    /// Classify the color into warm, cool or neutral by hue.
+    pub fn temperature(&self) -> ColorTemperature {
+        if self.get_transparency() {
+            return ColorTemperature::Neutral;
+        }
+
+        let (red, green, blue) = self.get_rgb();
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let saturation = if max == 0 {
+            0.0
+        } else {
+            (max - min) as f32 / max as f32
+        };
+
+        match self.hue() {
+            _ if saturation < NEUTRAL_SATURATION => ColorTemperature::Neutral,
+            None => ColorTemperature::Neutral,
+            Some(hue) if (90.0..300.0).contains(&hue) => ColorTemperature::Cool,
+            Some(_) => ColorTemperature::Warm,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, ColorTemperature};
+
+    #[test]
+    fn temperature_test() {
+        assert_eq!(
+            Color::new(255, 0, 0, false).temperature(),
+            ColorTemperature::Warm
+        );
+        assert_eq!(
+            Color::new(255, 200, 0, false).temperature(),
+            ColorTemperature::Warm
+        );
+        assert_eq!(
+            Color::new(0, 0, 255, false).temperature(),
+            ColorTemperature::Cool
+        );
+        assert_eq!(
+            Color::new(0, 200, 100, false).temperature(),
+            ColorTemperature::Cool
+        );
+        assert_eq!(
+            Color::new(128, 128, 128, false).temperature(),
+            ColorTemperature::Neutral
+        );
+        assert_eq!(
+            Color::new(130, 128, 128, false).temperature(),
+            ColorTemperature::Neutral
+        );
+        assert_eq!(
+            Color::new(255, 0, 0, true).temperature(),
+            ColorTemperature::Neutral
+        );
+    }
+}
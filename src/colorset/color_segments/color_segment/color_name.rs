@@ -12,12 +12,12 @@
 //!
 
 use crate::colorset::common;
-use bytemuck;
+use crate::error::ClsError;
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
 use bytes;
+use core::{error, fmt, ops};
 use nom;
 use serde;
-use std::{error, fmt, ops};
-use zerocopy::AsBytes;
 
 /// ColorName
 ///
@@ -28,6 +28,9 @@ pub struct ColorName {
 }
 
 impl ColorName {
+    /// Max bytes of the name encoded to utf16le.
+    pub const MAX_UTF16_BYTES: usize = 128;
+
     pub fn new() -> Self {
         ColorName {
             val: String::new(),
@@ -46,7 +49,7 @@ impl ColorName {
         let enc_utf16 = val.encode_utf16();
         let bytes_len_utf16 = enc_utf16.count() * 2;
 
-        if bytes_len_utf16 > 128 {
+        if bytes_len_utf16 > Self::MAX_UTF16_BYTES {
             Err(ColorNameError::EncodedStringOver128Bytes)
         } else {
             self.val = val.to_owned();
@@ -55,21 +58,128 @@ impl ColorName {
         }
     }
 
+    /// Set ColorName from the longest prefix of `val` that fits within 128 bytes of utf16le.
+    ///
+    /// Unlike ClipStudioPaint, a surrogate pair is never split, so the name stays valid utf16.
+    /// It is cut like [`ColorName::with_str_truncated`]. Returns the number of dropped chars.
+    pub fn set_str_truncated(&mut self, val: &str) -> usize {
+        let (color_name, _) = Self::with_str_truncated(val);
+        let dropped = val[color_name.val.len()..].chars().count();
+        *self = color_name;
+        dropped
+    }
+
+    /// Set ColorName from str with leading and trailing whitespace removed.
+    ///
+    /// # Note
+    /// Unicode whitespace is also removed. [`ColorName::set_str`] keeps the str as is.
+    pub fn set_str_trimmed(&mut self, val: &str) -> Result<(), ColorNameError> {
+        self.set_str(val.trim())
+    }
+
+    /// Create ColorName from utf16 code units.
+    ///
+    /// # Note
+    /// Unpaired surrogates, e.g. a high surrogate left at the end by a truncation, are rejected.
+    pub fn with_utf16(val: &[u16]) -> Result<Self, ColorNameError> {
+        let val = char::decode_utf16(val.iter().copied())
+            .collect::<Result<String, _>>()
+            .map_err(|_| ColorNameError::UnpairedSurrogate)?;
+
+        Self::with_str(&val)
+    }
+
+    /// Create ColorName from utf16le bytes, as they are in the cls file.
+    ///
+    /// # Note
+    /// Bytes over 128, of odd length, or with unpaired surrogates are rejected.
+    pub fn from_utf16le_bytes(val: &[u8]) -> Result<Self, ColorNameError> {
+        if val.len() > Self::MAX_UTF16_BYTES {
+            return Err(ColorNameError::EncodedStringOver128Bytes);
+        }
+        let pairs = val.chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err(ColorNameError::OddUtf16leBytes);
+        }
+
+        let val = pairs
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        Self::with_utf16(&val)
+    }
+
     pub fn validate_str(val: &str) -> Result<(), ColorNameError> {
         let enc_utf16 = val.encode_utf16();
         let bytes_len_utf16 = enc_utf16.count() * 2;
 
-        if bytes_len_utf16 > 128 {
+        if bytes_len_utf16 > Self::MAX_UTF16_BYTES {
             Err(ColorNameError::EncodedStringOver128Bytes)
         } else {
             Ok(())
         }
     }
+
+    /// Returns true if `val` can be set, see [`ColorName::validate_str`].
+    pub fn would_fit(val: &str) -> bool {
+        Self::validate_str(val).is_ok()
+    }
+
+    /// Returns how many more bytes of utf16le can be added to the name.
+    ///
+    /// # Note
+    /// A char is 2 bytes, or 4 bytes if it needs a surrogate pair.
+    pub fn remaining_utf16_bytes(&self) -> u16 {
+        (Self::MAX_UTF16_BYTES as u16).saturating_sub(self.bytes_len_utf16)
+    }
+
+    /// Create a ColorName, truncating `val` to 128 bytes of utf16le instead of failing.
+    ///
+    /// Returns true too if `val` was truncated. See [`ColorName::clamp_utf16_bytes`].
+    pub fn with_str_truncated(val: &str) -> (Self, bool) {
+        let mut color_name = ColorName {
+            val: val.to_owned(),
+            bytes_len_utf16: (val.encode_utf16().count() * 2).min(u16::MAX as usize) as u16,
+        };
+        let truncated = color_name.clamp_utf16_bytes(Self::MAX_UTF16_BYTES as u16);
+        (color_name, truncated)
+    }
+
+    /// Truncate the name so that it fits within `max_utf16_bytes` when encoded to utf16le.
+    ///
+    /// The name is cut on grapheme cluster boundaries, so no lone surrogates and no partial clusters remain.
+    /// Returns true if the name was truncated.
+    pub fn clamp_utf16_bytes(&mut self, max_utf16_bytes: u16) -> bool {
+        if self.bytes_len_utf16 <= max_utf16_bytes {
+            return false;
+        }
+
+        let truncated = truncate_utf16_bytes(&self.val, max_utf16_bytes as usize);
+        self.bytes_len_utf16 = (truncated.encode_utf16().count() * 2) as u16;
+        self.val.truncate(truncated.len());
+        true
+    }
+}
+
+/// Returns the longest prefix of `val` on grapheme cluster boundaries that fits within `max_utf16_bytes`.
+fn truncate_utf16_bytes(val: &str, max_utf16_bytes: usize) -> &str {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    let mut bytes_len_utf16 = 0;
+    let mut end = 0;
+    for (idx, grapheme) in val.grapheme_indices(true) {
+        bytes_len_utf16 += grapheme.encode_utf16().count() * 2;
+        if bytes_len_utf16 > max_utf16_bytes {
+            break;
+        }
+        end = idx + grapheme.len();
+    }
+
+    &val[..end]
 }
 
 impl common::ClsSize for ColorName {
-    fn size_in_cls(&self) -> u32 {
-        2 + self.size_contents_in_cls()
+    fn size_header(&self) -> common::SizeHeader {
+        common::SizeHeader::U16
     }
 
     fn size_contents_in_cls(&self) -> u32 {
@@ -80,8 +190,9 @@ impl common::ClsSize for ColorName {
 // ColorName into Cls bytes.
 impl common::ExtendBytesMut for ColorName {
     fn extend_bytes(&self, extended: &mut bytes::BytesMut) {
+        use common::ClsSection;
         // Extend bytesize header
-        extended.extend_from_slice(&self.bytes_len_utf16.as_bytes());
+        self.extend_size_header(extended);
 
         // Color Name(utf16le)
         let utf16_bytes_iter = self
@@ -96,35 +207,64 @@ impl common::ExtendBytesMut for ColorName {
 }
 
 impl common::TryFromBytes for ColorName {
-    fn try_from_bytes(input: &[u8]) -> nom::IResult<&[u8], Self>
+    fn try_from_bytes_with<'a>(
+        input: &'a [u8],
+        options: &common::ParseOptions,
+    ) -> nom::IResult<&'a [u8], Self, ClsError>
     where
         Self: Sized,
     {
         use nom::bytes::complete::take;
         use nom::number::complete::le_u16;
-        use nom::{
-            error::ErrorKind::Fail,
-            error::{Error, FromExternalError},
-            Err::Failure,
-        };
+        use nom::Err::Failure;
+
+        let (input, color_name_size) =
+            le_u16(input).map_err(common::unexpected_eof("ColorName", 2))?;
 
-        let (input, color_name_size) = le_u16(input)?;
+        let (input, color_name_bytes) = take(color_name_size as usize)(input).map_err(
+            common::unexpected_eof("ColorName", color_name_size as usize),
+        )?;
 
-        let (input, color_name_bytes) = take(color_name_size as usize)(input)?;
-        let color_name_u16_slice = bytemuck::try_cast_slice::<u8, u16>(color_name_bytes)
-            .map_err(|err| Failure(Error::from_external_error(input, Fail, err)))?;
-        let color_name_str = String::from_utf16(color_name_u16_slice)
-            .map_err(|err| Failure(Error::from_external_error(input, Fail, err)))?;
+        // utf16le, the bytes may not be aligned to u16.
+        let color_name_u16_vec = color_name_bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+
+        if options.lenient_names {
+            let (color_name, _) =
+                ColorName::with_str_truncated(&String::from_utf16_lossy(&color_name_u16_vec));
+            return Ok((input, color_name));
+        }
 
-        let mut color_name = ColorName::new();
-        color_name
-            .set_str(&color_name_str)
-            .map_err(|err| Failure(Error::from_external_error(input, Fail, err)))?;
+        if color_name_bytes.len() % 2 != 0 {
+            return Err(Failure(ClsError::InvalidUtf16));
+        }
+        let color_name =
+            ColorName::with_utf16(&color_name_u16_vec).map_err(|err| Failure(err.into()))?;
 
         Ok((input, color_name))
     }
 }
 
+/// Accepts both the serialized struct form and a plain string.
+impl<'de> serde::Deserialize<'de> for ColorName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Str(String),
+            Struct { val: String },
+        }
+
+        let (Repr::Str(val) | Repr::Struct { val }) = Repr::deserialize(deserializer)?;
+        ColorName::with_str(&val).map_err(serde::de::Error::custom)
+    }
+}
+
 impl ops::Deref for ColorName {
     type Target = String;
     fn deref(&self) -> &Self::Target {
@@ -135,14 +275,18 @@ impl ops::Deref for ColorName {
 #[derive(Debug)]
 pub enum ColorNameError {
     EncodedStringOver128Bytes,
+    UnpairedSurrogate,
+    OddUtf16leBytes,
 }
 
 impl fmt::Display for ColorNameError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", {
             use ColorNameError::*;
             match self {
                 EncodedStringOver128Bytes => "Encoded String in utf16 is over 128 bytes.",
+                UnpairedSurrogate => "String in utf16 has an unpaired surrogate.",
+                OddUtf16leBytes => "Bytes in utf16le have an odd length.",
             }
         })
     }
@@ -195,5 +339,165 @@ mod tests {
         assert!(clrnm
             .set_str(&(["a"; 63].concat() + utf8_char_4byte))
             .is_err());
+
+        assert!(clrnm
+            .set_str(&"a".repeat(ColorName::MAX_UTF16_BYTES / 2))
+            .is_ok());
+        assert!(clrnm
+            .set_str(&"a".repeat(ColorName::MAX_UTF16_BYTES / 2 + 1))
+            .is_err());
+    }
+
+    #[test]
+    fn truncated_test() {
+        use crate::error::ClsError;
+
+        let clrnm = ColorName::with_str("TESTCOLOR").unwrap();
+        let mut bytes = bytes::BytesMut::new();
+        clrnm.extend_bytes(&mut bytes);
+
+        let err = ColorName::try_from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Error(ClsError::UnexpectedEof {
+                expected: 18,
+                section: "ColorName"
+            })
+        ));
+    }
+
+    #[test]
+    fn set_str_truncated_test() {
+        let mut cn = ColorName::new();
+        assert_eq!(cn.set_str_truncated("Red"), 0);
+        assert_eq!(*cn, "Red");
+
+        // 🐙 would need the bytes 126..130, so it is dropped instead of leaving its high surrogate
+        let val = ["A"; 63].concat() + "🐙BC";
+        assert_eq!(cn.set_str_truncated(&val), 3);
+        assert_eq!(*cn, ["A"; 63].concat());
+        assert!(ColorName::validate_str(&cn).is_ok());
+
+        let val = ["A"; 62].concat() + "🐙BC";
+        assert_eq!(cn.set_str_truncated(&val), 2);
+        assert_eq!(*cn, ["A"; 62].concat() + "🐙");
+        assert_eq!(cn, ColorName::with_str(&cn).unwrap());
+
+        // a combining accent is not left without its base char
+        let val = ["A"; 63].concat() + "e\u{301}";
+        assert_eq!(cn.set_str_truncated(&val), 2);
+        assert_eq!(cn, ColorName::with_str_truncated(&val).0);
+    }
+
+    #[test]
+    fn set_str_trimmed_test() {
+        let mut cn = ColorName::new();
+        cn.set_str_trimmed("  Red  ").unwrap();
+        assert_eq!(*cn, "Red");
+
+        cn.set_str_trimmed("\u{3000}Blue\t\n").unwrap();
+        assert_eq!(*cn, "Blue");
+
+        cn.set_str(" Green ").unwrap();
+        assert_eq!(*cn, " Green ");
+    }
+
+    #[test]
+    fn lenient_names_option_test() {
+        use crate::colorset::common::ParseOptions;
+
+        // "A" + lone surrogate
+        let lone_surrogate = [4, 0, 0x41, 0, 0x00, 0xD8];
+        assert!(ColorName::try_from_bytes(&lone_surrogate).is_err());
+
+        let options = ParseOptions {
+            lenient_names: true,
+            ..Default::default()
+        };
+        let (_, clrnm) = ColorName::try_from_bytes_with(&lone_surrogate, &options).unwrap();
+        assert_eq!(*clrnm, "A\u{FFFD}");
+
+        // 65 utf16 chars
+        let mut too_long = vec![130, 0];
+        too_long.extend("a".repeat(65).encode_utf16().flat_map(u16::to_le_bytes));
+        assert!(ColorName::try_from_bytes(&too_long).is_err());
+        let (_, clrnm) = ColorName::try_from_bytes_with(&too_long, &options).unwrap();
+        assert_eq!(*clrnm, "a".repeat(64));
+    }
+
+    #[test]
+    fn unpaired_surrogate_test() {
+        use super::ColorNameError;
+        use crate::error::ClsError;
+
+        // "A" + high surrogate of U+1F5FF
+        assert!(matches!(
+            ColorName::with_utf16(&[0x41, 0xD83D]),
+            Err(ColorNameError::UnpairedSurrogate)
+        ));
+        assert_eq!(
+            *ColorName::with_utf16(&[0x41, 0xD83D, 0xDDFF]).unwrap(),
+            "A\u{1F5FF}"
+        );
+
+        let high_surrogate_bytes = [4, 0, 0x41, 0, 0x3D, 0xD8];
+        assert!(matches!(
+            ColorName::try_from_bytes(&high_surrogate_bytes),
+            Err(nom::Err::Failure(ClsError::ColorName(
+                ColorNameError::UnpairedSurrogate
+            )))
+        ));
+    }
+
+    #[test]
+    fn remaining_test() {
+        let mut clrnm = ColorName::new();
+        assert_eq!(clrnm.remaining_utf16_bytes(), 128);
+
+        clrnm.set_str("a\u{1f5ff}").unwrap();
+        assert_eq!(clrnm.remaining_utf16_bytes(), 122);
+        assert!(ColorName::would_fit(&"a".repeat(61)));
+        assert!(!ColorName::would_fit(&"a".repeat(65)));
+
+        clrnm.set_str(&"a".repeat(64)).unwrap();
+        assert_eq!(clrnm.remaining_utf16_bytes(), 0);
+    }
+
+    #[test]
+    fn from_utf16le_bytes_test() {
+        use super::ColorNameError;
+
+        let color_name = ColorName::from_utf16le_bytes(&[0x41, 0, 0x3D, 0xD8, 0xFF, 0xDD]).unwrap();
+        assert_eq!(color_name, ColorName::with_str("A\u{1F5FF}").unwrap());
+        let mut bytes = bytes::BytesMut::new();
+        color_name.extend_bytes(&mut bytes);
+        assert_eq!(bytes.as_ref(), [6, 0, 0x41, 0, 0x3D, 0xD8, 0xFF, 0xDD]);
+        assert!(ColorName::from_utf16le_bytes(&[0x41, 0].repeat(64)).is_ok());
+
+        assert!(matches!(
+            ColorName::from_utf16le_bytes(&[0x41, 0].repeat(65)),
+            Err(ColorNameError::EncodedStringOver128Bytes)
+        ));
+        assert!(matches!(
+            ColorName::from_utf16le_bytes(&[0x41, 0, 0x41]),
+            Err(ColorNameError::OddUtf16leBytes)
+        ));
+        assert!(matches!(
+            ColorName::from_utf16le_bytes(&[0x41, 0, 0x3D, 0xD8]),
+            Err(ColorNameError::UnpairedSurrogate)
+        ));
+    }
+
+    #[test]
+    fn deserialize_test() {
+        let clrnm: ColorName = serde_json::from_str("\"TESTCOLOR\"").unwrap();
+        assert_eq!(clrnm, ColorName::with_str("TESTCOLOR").unwrap());
+
+        let se_clrnm = serde_json::to_string(&clrnm).unwrap();
+        let de_clrnm: ColorName = serde_json::from_str(&se_clrnm).unwrap();
+        assert_eq!(de_clrnm, clrnm);
+
+        let too_long = format!("\"{}\"", "t".repeat(65));
+        assert!(serde_json::from_str::<ColorName>(&too_long).is_err());
     }
 }
@@ -1,23 +1,53 @@
 //! Colorset Name
 //!
 //!
+//! # Note
+//! The name is written both in Shift-JIS and in utf8, but only the utf8 one is read.
+//! Without the `sjis` feature, the Shift-JIS block is written empty.
 
 use crate::colorset::common;
+use crate::error::ClsError;
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
 use bytes;
+use core::{error, fmt, ops};
+#[cfg(feature = "sjis")]
 use encoding_rs as enc;
 use nom;
 use serde;
-use std::{error, fmt, ops};
 use zerocopy::AsBytes;
 
-#[derive(Debug, PartialEq, serde::Serialize)]
+/// ColorsetName
+///
+/// # Note
+/// Equality only compares the name, not the raw delimiter,
+/// which is not serialized and is 0 again after a serde roundtrip.
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct ColorsetName {
     val: String,
+    /// u32 between the sjis and utf8 blocks, which is written back unchanged.
+    #[serde(skip)]
+    delimiter: u32,
 }
 
 impl ColorsetName {
+    /// Max bytes of the name in utf8.
+    pub const MAX_UTF8_BYTES: usize = 192;
+    /// Max number of chars, a 4bytes char is counted as 2.
+    pub const MAX_CHAR_COUNT: usize = 64;
+
     pub fn new() -> Self {
-        ColorsetName { val: String::new() }
+        ColorsetName {
+            val: String::new(),
+            delimiter: 0,
+        }
+    }
+
+    /// Returns the u32 between the sjis and utf8 blocks.
+    ///
+    /// # Note
+    /// Its meaning is unknown. It is 0 in the files known so far, and for new names.
+    pub fn raw_delimiter(&self) -> u32 {
+        self.delimiter
     }
 
     /// Set ColorsetName from str
@@ -30,18 +60,12 @@ impl ColorsetName {
     pub fn set_str(&mut self, val: &str) -> Result<(), ColorsetNameError> {
         use ColorsetNameError::*;
         // bytes check
-        if val.len() > 192 {
+        if val.len() > Self::MAX_UTF8_BYTES {
             return Err(StringOver192Bytes);
         }
 
         // char count check
-        let count = val.chars().into_iter().fold(0usize, |cn, c| {
-            cn + match c.len_utf8() {
-                4 => 2,
-                _ => 1,
-            }
-        });
-        if count > 64 {
+        if count_chars(val) > Self::MAX_CHAR_COUNT {
             return Err(CharCountExceeded64);
         }
 
@@ -49,13 +73,69 @@ impl ColorsetName {
         Ok(())
     }
 
+    /// Set ColorsetName from str, truncating `val` to the restrictions of [`ColorsetName::set_str`] instead of failing.
+    ///
+    /// Returns true if `val` was truncated.
+    pub fn set_str_truncated(&mut self, val: &str) -> bool {
+        let truncated = truncate_to_limits(val);
+        self.val = truncated.to_owned();
+        truncated.len() != val.len()
+    }
+
+    /// Returns how many more utf8 bytes can be added to the name.
+    pub fn remaining_bytes(&self) -> u16 {
+        (Self::MAX_UTF8_BYTES as u16).saturating_sub(self.val.len() as u16)
+    }
+
+    /// Returns how many more chars of 3bytes or less can be added to the name.
+    ///
+    /// # Note
+    /// A 4bytes char consumes two of them.
+    /// The bytes limit can be reached first, see [`ColorsetName::remaining_bytes`].
+    pub fn remaining_chars(&self) -> u8 {
+        (Self::MAX_CHAR_COUNT as u8).saturating_sub(count_chars(&self.val) as u8)
+    }
+
+    /// Returns how many more chars can be added to the name, where a 4bytes char costs two.
+    ///
+    /// # Note
+    /// Same as [`ColorsetName::remaining_chars`], for front-ends counting in usize.
+    pub fn remaining_char_budget(&self) -> usize {
+        self.remaining_chars() as usize
+    }
+
     /// Encode utf8 to sjis
     ///
     /// # Note
     /// This method conforms to the cls file specification.
     ///     - 4 bytes unmappable utf8 char is converted to "2 whitespace"(0x20,0x20).
     ///     - Less than 4 bytes unmappable utf8 char is converted to "whitespace"(0x20).
-    fn encode_sjis(&self) -> Vec<u8> {
+    pub(crate) fn encode_sjis(&self) -> Vec<u8> {
+        self.encode_sjis_reporting().0
+    }
+
+    /// Returns the chars that are not mappable to sjis, in order of appearance.
+    ///
+    /// They are replaced with whitespace in the sjis block.
+    #[cfg(feature = "sjis")]
+    pub fn unmappable_sjis_chars(&self) -> Vec<char> {
+        self.encode_sjis_reporting().1
+    }
+
+    /// Encode utf8 to sjis without the whitespace replacement of [`ColorsetName::encode_sjis`].
+    ///
+    /// Returns the unmappable chars in order of appearance if any, e.g. to warn that they won't show in older ClipStudioPaint.
+    #[cfg(feature = "sjis")]
+    pub fn encode_sjis_checked(&self) -> Result<Vec<u8>, Vec<char>> {
+        match self.encode_sjis_reporting() {
+            (sjis_buf, unmappable_chars) if unmappable_chars.is_empty() => Ok(sjis_buf),
+            (_, unmappable_chars) => Err(unmappable_chars),
+        }
+    }
+
+    /// Encode utf8 to sjis, also returning the unmappable chars.
+    #[cfg(feature = "sjis")]
+    fn encode_sjis_reporting(&self) -> (Vec<u8>, Vec<char>) {
         use enc::EncoderResult::*;
 
         //println!("input - {}", str);
@@ -64,6 +144,7 @@ impl ColorsetName {
         let chars_count = self.val.chars().count();
         let mut sjis_buf: Vec<u8> = Vec::with_capacity(chars_count * 2);
 
+        let mut unmappable_chars = Vec::new();
         let mut input_str = self.val.as_ref();
 
         loop {
@@ -74,6 +155,7 @@ impl ColorsetName {
                 InputEmpty => break,
                 OutputFull => break, // unreachable,
                 Unmappable(c) => {
+                    unmappable_chars.push(c);
                     match c.len_utf8() {
                         // Conforms to the cls file specification.
                         4 => {
@@ -93,13 +175,42 @@ impl ColorsetName {
         }
 
         //println!("output - {:02x?}", sjis_buf);
-        sjis_buf
+        (sjis_buf, unmappable_chars)
+    }
+
+    /// Without the `sjis` feature, the sjis block is empty.
+    #[cfg(not(feature = "sjis"))]
+    fn encode_sjis_reporting(&self) -> (Vec<u8>, Vec<char>) {
+        (Vec::new(), Vec::new())
     }
 }
 
+/// Count chars, a 4bytes char is counted as 2.
+fn count_chars(val: &str) -> usize {
+    val.chars().fold(0usize, |cn, c| {
+        cn + match c.len_utf8() {
+            4 => 2,
+            _ => 1,
+        }
+    })
+}
+
+/// Returns the longest prefix of `val` that satisfies the restrictions of [`ColorsetName::set_str`].
+fn truncate_to_limits(val: &str) -> &str {
+    let mut count = 0usize;
+    for (idx, c) in val.char_indices() {
+        count += count_chars(c.encode_utf8(&mut [0; 4]));
+        if count > ColorsetName::MAX_CHAR_COUNT || idx + c.len_utf8() > ColorsetName::MAX_UTF8_BYTES
+        {
+            return &val[..idx];
+        }
+    }
+    val
+}
+
 impl common::ClsSize for ColorsetName {
-    fn size_in_cls(&self) -> u32 {
-        4 + self.size_contents_in_cls()
+    fn size_header(&self) -> common::SizeHeader {
+        common::SizeHeader::U32
     }
 
     fn size_contents_in_cls(&self) -> u32 {
@@ -114,6 +225,7 @@ impl common::ClsSize for ColorsetName {
 
 impl common::ExtendBytesMut for ColorsetName {
     fn extend_bytes(&self, extended: &mut bytes::BytesMut) {
+        use common::ClsSize;
         // sjis
         let sjis_buf = self.encode_sjis();
         let sjis_buf_size = sjis_buf.len() as u16;
@@ -122,18 +234,16 @@ impl common::ExtendBytesMut for ColorsetName {
         let utf8_buf = self.as_bytes();
         let utf8_buf_size = self.len() as u16;
 
-        // ColorsetName bytesize header
-        let bytesize_header: u32 = 8 + sjis_buf_size as u32 + utf8_buf_size as u32;
-
-        // extend bytesize header
-        extended.extend_from_slice(bytesize_header.as_bytes());
+        // extend bytesize header, reusing the sjis encoded above
+        self.size_header()
+            .extend(8 + sjis_buf_size as u32 + utf8_buf_size as u32, extended);
 
         // extend sjis
         extended.extend_from_slice(sjis_buf_size.as_bytes());
         extended.extend_from_slice(&sjis_buf);
 
         // delimiter?
-        extended.extend_from_slice(0u32.as_bytes());
+        extended.extend_from_slice(self.delimiter.as_bytes());
 
         // extend utf8
         extended.extend_from_slice(utf8_buf_size.as_bytes());
@@ -142,41 +252,87 @@ impl common::ExtendBytesMut for ColorsetName {
 }
 
 impl common::TryFromBytes for ColorsetName {
-    fn try_from_bytes(input: &[u8]) -> nom::IResult<&[u8], Self>
+    fn try_from_bytes_with<'a>(
+        input: &'a [u8],
+        options: &common::ParseOptions,
+    ) -> nom::IResult<&'a [u8], Self, ClsError>
     where
         Self: Sized,
     {
+        use common::{check_size, unexpected_eof};
         use nom::bytes::complete::take;
         use nom::number::complete::{le_u16, le_u32};
-        use nom::{error::Error, error::ErrorKind::Fail, error::FromExternalError, Err::Failure};
+        use nom::Err::Failure;
         // get colorsetname bytesize header
-        let (input, _) = le_u32(input)?;
+        let (input, size) = le_u32(input).map_err(unexpected_eof("ColorsetName", 4))?;
+        let contents = input;
 
         // get sjis name bytesize
-        let (input, sjis_bytes_size) = le_u16(input)?;
+        let (input, sjis_bytes_size) = le_u16(input).map_err(unexpected_eof("ColorsetName", 2))?;
         // ignore sjis bytes
-        let (input, _) = take(sjis_bytes_size as usize)(input)?;
-        // ignore delimiter
-        let (input, _) = le_u32(input)?;
+        let (input, _) = take(sjis_bytes_size as usize)(input)
+            .map_err(unexpected_eof("ColorsetName", sjis_bytes_size as usize))?;
+        // delimiter?
+        let (input, delimiter) = le_u32(input).map_err(unexpected_eof("ColorsetName", 4))?;
         // get utf8 name bytesize
-        let (input, utf8_bytes_size) = le_u16(input)?;
+        let (input, utf8_bytes_size) = le_u16(input).map_err(unexpected_eof("ColorsetName", 2))?;
         // get utf8 bytes
-        let (input, utf8_bytes) = take(utf8_bytes_size as usize)(input)?;
+        let (input, utf8_bytes) = take(utf8_bytes_size as usize)(input)
+            .map_err(unexpected_eof("ColorsetName", utf8_bytes_size as usize))?;
+
+        check_size("ColorsetName", size, contents.len() - input.len(), options)?;
+
+        if options.lenient_names {
+            let colorset_name_str = String::from_utf8_lossy(utf8_bytes);
+            let mut colorset_name = ColorsetName::new();
+            colorset_name.set_str_truncated(&colorset_name_str);
+            colorset_name.delimiter = delimiter;
+            return Ok((input, colorset_name));
+        }
 
         // conv string
-        let colorset_name_str = String::from_utf8(utf8_bytes.to_owned())
-            .map_err(|err| Failure(Error::from_external_error(input, Fail, err)))?;
+        let colorset_name_str =
+            String::from_utf8(utf8_bytes.to_owned()).map_err(|err| Failure(err.into()))?;
 
         // make ColorsetName
         let mut colorset_name = ColorsetName::new();
         colorset_name
             .set_str(&colorset_name_str)
-            .map_err(|err| Failure(Error::from_external_error(input, Fail, err)))?;
+            .map_err(|err| Failure(err.into()))?;
+        colorset_name.delimiter = delimiter;
 
         Ok((input, colorset_name))
     }
 }
 
+/// Accepts both the serialized struct form and a plain string.
+impl<'de> serde::Deserialize<'de> for ColorsetName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Str(String),
+            Struct { val: String },
+        }
+
+        let (Repr::Str(val) | Repr::Struct { val }) = Repr::deserialize(deserializer)?;
+        let mut colorset_name = ColorsetName::new();
+        colorset_name
+            .set_str(&val)
+            .map_err(serde::de::Error::custom)?;
+        Ok(colorset_name)
+    }
+}
+
+impl PartialEq for ColorsetName {
+    fn eq(&self, other: &Self) -> bool {
+        self.val == other.val
+    }
+}
+
 impl ops::Deref for ColorsetName {
     type Target = String;
     fn deref(&self) -> &Self::Target {
@@ -191,7 +347,7 @@ pub enum ColorsetNameError {
 }
 
 impl fmt::Display for ColorsetNameError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", {
             use ColorsetNameError::*;
             match self {
@@ -237,4 +393,180 @@ mod tests {
                 assert_eq!(de_csn, csn);
             });
     }
+
+    #[test]
+    fn char_count_boundary_test() {
+        use super::ColorsetNameError::CharCountExceeded64;
+
+        let emoji = "\u{1f5ff}";
+        let mut csn = ColorsetName::new();
+
+        // 64 chars
+        assert!(csn.set_str(&"t".repeat(64)).is_ok());
+        // 63 + 2 = 65 chars
+        assert!(matches!(
+            csn.set_str(&format!("{}{}", "t".repeat(63), emoji)),
+            Err(CharCountExceeded64)
+        ));
+        // 32 * 2 = 64 chars
+        assert!(csn.set_str(&emoji.repeat(32)).is_ok());
+        // 33 * 2 = 66 chars
+        assert!(matches!(
+            csn.set_str(&emoji.repeat(33)),
+            Err(CharCountExceeded64)
+        ));
+
+        // The failed set_str keeps the last valid name.
+        assert_eq!(*csn, emoji.repeat(32));
+    }
+
+    #[test]
+    fn raw_delimiter_test() {
+        let mut csn = ColorsetName::new();
+        csn.set_str("testset").unwrap();
+        assert_eq!(csn.raw_delimiter(), 0);
+
+        let mut byte_csn = bytes::BytesMut::new();
+        csn.extend_bytes(&mut byte_csn);
+        let delimiter_pos = 4 + 2 + csn.encode_sjis().len();
+        assert_eq!(byte_csn[delimiter_pos..delimiter_pos + 4], [0, 0, 0, 0]);
+        byte_csn[delimiter_pos..delimiter_pos + 4].copy_from_slice(&7u32.to_le_bytes());
+
+        let (_, de_csn) = ColorsetName::try_from_bytes(byte_csn.as_ref()).unwrap();
+        assert_eq!(de_csn.raw_delimiter(), 7);
+        // the delimiter is not part of equality
+        assert_eq!(de_csn, csn);
+
+        let mut re_byte_csn = bytes::BytesMut::new();
+        de_csn.extend_bytes(&mut re_byte_csn);
+        assert_eq!(re_byte_csn, byte_csn);
+
+        // the delimiter is dropped by serde, but the roundtrip is still equal
+        let json_csn: ColorsetName =
+            serde_json::from_str(&serde_json::to_string(&de_csn).unwrap()).unwrap();
+        assert_eq!(json_csn.raw_delimiter(), 0);
+        assert_eq!(json_csn, de_csn);
+    }
+
+    #[test]
+    fn truncated_test() {
+        use crate::error::ClsError;
+
+        let mut csn = ColorsetName::new();
+        csn.set_str("testset").unwrap();
+        let mut byte_csn = bytes::BytesMut::new();
+        csn.extend_bytes(&mut byte_csn);
+
+        let err = ColorsetName::try_from_bytes(&byte_csn[..byte_csn.len() - 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            nom::Err::Error(ClsError::UnexpectedEof {
+                expected: 7,
+                section: "ColorsetName"
+            })
+        ));
+    }
+
+    #[test]
+    fn remaining_test() {
+        let mut csn = ColorsetName::new();
+        assert_eq!(csn.remaining_bytes() as usize, ColorsetName::MAX_UTF8_BYTES);
+        assert_eq!(csn.remaining_chars() as usize, ColorsetName::MAX_CHAR_COUNT);
+
+        csn.set_str(&"a".repeat(30)).unwrap();
+        assert_eq!(csn.remaining_bytes(), 162);
+        assert_eq!(csn.remaining_chars(), 34);
+
+        csn.set_str(&format!("{}\u{1f5ff}", "あ".repeat(29)))
+            .unwrap();
+        assert_eq!(csn.remaining_bytes(), 192 - 29 * 3 - 4);
+        assert_eq!(csn.remaining_chars(), 64 - 29 - 2);
+        assert_eq!(csn.remaining_char_budget(), 64 - 29 - 2);
+    }
+
+    #[test]
+    fn parse_options_test() {
+        use crate::colorset::common::ParseOptions;
+        use crate::error::ClsError;
+
+        let mut csn = ColorsetName::new();
+        csn.set_str("testset").unwrap();
+        let mut byte_csn = bytes::BytesMut::new();
+        csn.extend_bytes(&mut byte_csn);
+
+        // broken size header
+        byte_csn[0] -= 1;
+        assert!(ColorsetName::try_from_bytes(&byte_csn).is_ok());
+        let options = ParseOptions {
+            check_sizes: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ColorsetName::try_from_bytes_with(&byte_csn, &options),
+            Err(nom::Err::Failure(ClsError::SizeMismatch {
+                section: "ColorsetName",
+                ..
+            }))
+        ));
+
+        // 32 ascii chars and 17 4bytes chars, 66 chars in total
+        let too_long = format!("{}{}", "t".repeat(32), "\u{1f5ff}".repeat(17));
+        let mut byte_csn = bytes::BytesMut::new();
+        byte_csn.extend_from_slice(&(10 + too_long.len() as u32).to_le_bytes());
+        byte_csn.extend_from_slice(&[2, 0, 0x20, 0x20, 0, 0, 0, 0]);
+        byte_csn.extend_from_slice(&(too_long.len() as u16).to_le_bytes());
+        byte_csn.extend_from_slice(too_long.as_bytes());
+        assert!(ColorsetName::try_from_bytes(&byte_csn).is_err());
+
+        let options = ParseOptions {
+            lenient_names: true,
+            ..Default::default()
+        };
+        let (_, de_csn) = ColorsetName::try_from_bytes_with(&byte_csn, &options).unwrap();
+        assert_eq!(
+            *de_csn,
+            format!("{}{}", "t".repeat(32), "\u{1f5ff}".repeat(16))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sjis")]
+    fn unmappable_sjis_chars_test() {
+        let mut csn = ColorsetName::new();
+        csn.set_str("\u{6F22}\u{5B57}\u{1f5ff}test\u{1f600}")
+            .unwrap();
+        assert_eq!(csn.unmappable_sjis_chars(), vec!['\u{1f5ff}', '\u{1f600}']);
+
+        csn.set_str("\u{6F22}\u{5B57}test").unwrap();
+        assert!(csn.unmappable_sjis_chars().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "sjis")]
+    fn encode_sjis_checked_test() {
+        let mut csn = ColorsetName::new();
+        csn.set_str("\u{6F22}a").unwrap();
+        assert_eq!(csn.encode_sjis_checked(), Ok(vec![0x8A, 0xBF, 0x61]));
+        assert_eq!(csn.encode_sjis_checked().unwrap(), csn.encode_sjis());
+
+        csn.set_str("a\u{1f5ff}\u{0E01}").unwrap();
+        assert_eq!(
+            csn.encode_sjis_checked(),
+            Err(vec!['\u{1f5ff}', '\u{0E01}'])
+        );
+        assert_eq!(csn.encode_sjis(), vec![0x61, 0x20, 0x20, 0x20]);
+    }
+
+    #[test]
+    fn deserialize_test() {
+        let csn: ColorsetName = serde_json::from_str("\"testset\"").unwrap();
+        assert_eq!(*csn, "testset");
+
+        let se_csn = serde_json::to_string(&csn).unwrap();
+        let de_csn: ColorsetName = serde_json::from_str(&se_csn).unwrap();
+        assert_eq!(de_csn, csn);
+
+        let too_long = format!("\"{}\"", "t".repeat(65));
+        assert!(serde_json::from_str::<ColorsetName>(&too_long).is_err());
+    }
 }
@@ -1,13 +1,29 @@
 //! Common
 
-use bytes::BytesMut;
+use crate::error::ClsError;
+use bytes::{Bytes, BytesMut};
 use nom;
+#[cfg(feature = "std")]
+use std::io;
+use zerocopy::AsBytes;
 /// ExtendBytesMut Trait
 ///
 ///
 pub trait ExtendBytesMut {
     /// Append to given BytesMut.
     fn extend_bytes(&self, extended: &mut BytesMut);
+
+    /// Write the same bytes as [`Self::extend_bytes`] to `writer`.
+    ///
+    /// # Note
+    /// If not overridden, the whole bytes are built in memory first.
+    /// Sections made of other sections override it to write them one by one.
+    #[cfg(feature = "std")]
+    fn write_to(&self, writer: &mut dyn io::Write) -> io::Result<()> {
+        let mut extended = BytesMut::new();
+        self.extend_bytes(&mut extended);
+        writer.write_all(&extended)
+    }
 }
 
 /// TryFromBytes
@@ -16,20 +32,238 @@ pub trait TryFromBytes {
     /// Input bytes try into Self.
     ///
     /// Using nom
-    fn try_from_bytes(input: &[u8]) -> nom::IResult<&[u8], Self>
+    fn try_from_bytes(input: &[u8]) -> nom::IResult<&[u8], Self, ClsError>
+    where
+        Self: Sized,
+    {
+        Self::try_from_bytes_with(input, &ParseOptions::default())
+    }
+
+    /// Input bytes try into Self with `options`, which are passed down to the inner sections.
+    fn try_from_bytes_with<'a>(
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> nom::IResult<&'a [u8], Self, ClsError>
     where
         Self: Sized;
 }
 
+/// ParseOptions
+///
+/// Options of [`TryFromBytes::try_from_bytes_with`].
+/// [`Default`] is the behavior of [`TryFromBytes::try_from_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Reject size headers that differ from the actual size of the section. Default is false.
+    pub check_sizes: bool,
+    /// Decode invalid strings lossily and truncate too long names, instead of failing. Default is false.
+    pub lenient_names: bool,
+    /// Accept bytes after the color segments of a colorset. Default is true.
+    pub allow_trailing_bytes: bool,
+    /// Has no effect, the alpha byte of colors is always kept. Default is false.
+    #[deprecated(note = "the alpha byte of colors is always kept")]
+    pub capture_alpha: bool,
+    /// Reject color name flags other than 0 and 1. Default is false, and they are treated as no name.
+    pub strict_name_flag: bool,
+}
+
+impl Default for ParseOptions {
+    #[allow(deprecated)]
+    fn default() -> Self {
+        ParseOptions {
+            check_sizes: false,
+            lenient_names: false,
+            allow_trailing_bytes: true,
+            capture_alpha: false,
+            strict_name_flag: false,
+        }
+    }
+}
+
+/// Result of a parser whose error comes with the offset of the section that failed.
+pub(crate) type OffsetResult<'a, T> = Result<(&'a [u8], T), (usize, nom::Err<ClsError>)>;
+
+/// Check the size header of `section` against the number of bytes actually consumed.
+pub(crate) fn check_size(
+    section: &'static str,
+    expected: u32,
+    found: usize,
+    options: &ParseOptions,
+) -> Result<(), nom::Err<ClsError>> {
+    if options.check_sizes && expected as usize != found {
+        Err(nom::Err::Failure(ClsError::SizeMismatch {
+            section,
+            expected,
+            found,
+        }))
+    } else {
+        Ok(())
+    }
+}
+
+/// Map the end of input error of a nom parser into [`ClsError::UnexpectedEof`].
+///
+/// `expected` is the number of bytes the parser tried to read in `section`.
+pub(crate) fn unexpected_eof(
+    section: &'static str,
+    expected: usize,
+) -> impl Fn(nom::Err<ClsError>) -> nom::Err<ClsError> {
+    use nom::{error::ErrorKind::Eof, Err};
+    move |err| match err {
+        Err::Error(ClsError::Nom(Eof)) | Err::Incomplete(_) => {
+            Err::Error(ClsError::UnexpectedEof { expected, section })
+        }
+        Err::Failure(ClsError::Nom(Eof)) => {
+            Err::Failure(ClsError::UnexpectedEof { expected, section })
+        }
+        err => err,
+    }
+}
+
+/// SizeHeader
+///
+/// Width of the size header written before the contents of a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeHeader {
+    None,
+    U16,
+    U32,
+}
+
+impl SizeHeader {
+    /// Returns the byte size of the header.
+    pub fn byte_size(&self) -> u32 {
+        match self {
+            SizeHeader::None => 0,
+            SizeHeader::U16 => 2,
+            SizeHeader::U32 => 4,
+        }
+    }
+
+    /// Append the header of `contents_size` to given BytesMut.
+    pub fn extend(&self, contents_size: u32, extended: &mut BytesMut) {
+        match self {
+            SizeHeader::None => {}
+            SizeHeader::U16 => extended.extend_from_slice((contents_size as u16).as_bytes()),
+            SizeHeader::U32 => extended.extend_from_slice(contents_size.as_bytes()),
+        }
+    }
+}
+
 /// ClsSize Trait
 pub trait ClsSize {
     /// Returns the byte size in the cls file, not including the size header.
     fn size_contents_in_cls(&self) -> u32;
 
+    /// Returns the size header of this section.
+    /// # Note
+    /// If not overridden, it is [`SizeHeader::None`].
+    fn size_header(&self) -> SizeHeader {
+        SizeHeader::None
+    }
+
     /// Returns the byte size in the cls file, including the size header.
     /// # Note
-    /// If not overridden, it is a same [`Self::size_contents_in_cls`]
+    /// If not overridden, it is [`Self::size_contents_in_cls`] plus the byte size of [`Self::size_header`].
     fn size_in_cls(&self) -> u32 {
-        self.size_contents_in_cls()
+        self.size_header().byte_size() + self.size_contents_in_cls()
+    }
+}
+
+/// ClsSection Trait
+///
+/// A section of the cls file layout, which can be sized, serialized and parsed.
+/// Implemented for all types implementing [`ClsSize`], [`ExtendBytesMut`] and [`TryFromBytes`].
+pub trait ClsSection: ClsSize + ExtendBytesMut + TryFromBytes {
+    /// Append to given BytesMut, reserving the section size in advance.
+    fn extend_into(&self, extended: &mut BytesMut) {
+        extended.reserve(self.size_in_cls() as usize);
+        self.extend_bytes(extended);
+    }
+
+    /// Append the [`ClsSize::size_header`] of this section, which is the size of the contents.
+    fn extend_size_header(&self, extended: &mut BytesMut) {
+        self.size_header()
+            .extend(self.size_contents_in_cls(), extended);
+    }
+
+    /// Returns the bytes of this section in the cls file.
+    fn as_cls_bytes(&self) -> Bytes {
+        let mut section_bytes = BytesMut::with_capacity(self.size_in_cls() as usize);
+        self.extend_bytes(&mut section_bytes);
+
+        section_bytes.freeze()
+    }
+}
+
+impl<T: ClsSize + ExtendBytesMut + TryFromBytes> ClsSection for T {}
+
+/// Parse a section of type `T` from input bytes.
+pub fn parse_section<T: ClsSection>(input: &[u8]) -> nom::IResult<&[u8], T, ClsError> {
+    T::try_from_bytes(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_section, ClsSection, ClsSize, SizeHeader};
+    use crate::colorset::color_segments::color_segment::{
+        color::Color, color_name::ColorName, ColorSegment,
+    };
+    use crate::colorset::colorset_name::ColorsetName;
+
+    fn roundtrip<T: ClsSection + PartialEq + std::fmt::Debug>(section: T) {
+        let section_bytes = section.as_cls_bytes();
+        assert_eq!(section_bytes.len() as u32, section.size_in_cls());
+
+        let (rest, de_section) = parse_section::<T>(&section_bytes).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(de_section, section);
+    }
+
+    #[test]
+    fn section_test() {
+        roundtrip(Color::new(1, 128, 255, false));
+        roundtrip(ColorName::with_str("TESTCOLOR").unwrap());
+        roundtrip(ColorSegment::with_val(1, 128, 255, false, Some("TESTCOLOR")).unwrap());
+        let mut colorset_name = ColorsetName::new();
+        colorset_name.set_str("testset").unwrap();
+        roundtrip(colorset_name);
+
+        // trait object
+        let sections: Vec<Box<dyn ClsSection>> = vec![
+            Box::new(Color::new(1, 128, 255, false)),
+            Box::new(ColorName::with_str("TESTCOLOR").unwrap()),
+        ];
+        let mut extended = bytes::BytesMut::new();
+        sections.iter().for_each(|s| s.extend_into(&mut extended));
+        assert_eq!(extended.len(), 4 + 2 + 18);
+
+        let (input, color) = parse_section::<Color>(&extended).unwrap();
+        let (input, color_name) = parse_section::<ColorName>(input).unwrap();
+        assert!(input.is_empty());
+        assert_eq!(color.get_hex_color(true), "#0180FF");
+        assert_eq!(*color_name, "TESTCOLOR");
+    }
+
+    #[test]
+    fn size_header_test() {
+        let mut extended = bytes::BytesMut::new();
+        ColorName::with_str("TESTCOLOR")
+            .unwrap()
+            .extend_size_header(&mut extended);
+        assert_eq!(extended.as_ref(), [18, 0]);
+
+        extended.clear();
+        ColorSegment::with_val(1, 128, 255, false, None)
+            .unwrap()
+            .extend_size_header(&mut extended);
+        assert_eq!(extended.as_ref(), [8, 0, 0, 0]);
+
+        // no size header
+        extended.clear();
+        let color = Color::new(1, 128, 255, false);
+        assert_eq!(color.size_header(), SizeHeader::None);
+        color.extend_size_header(&mut extended);
+        assert!(extended.is_empty());
     }
 }
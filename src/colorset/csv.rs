@@ -0,0 +1,248 @@
+//! CSV
+//!
+//! Spreadsheet friendly text of `name,hex,transparent` rows.
+//!
+//! ```text
+//! name,hex,transparent
+//! Color0,#000000,true
+//! Orange,#FF8000,false
+//! ,#0A0B0C,false
+//! ```
+//!
+//! # Note
+//! - Fields containing `,`, `"` or line breaks are quoted, and `"` in them is doubled.
+//!   A quoted field may span lines.
+//! - The first row is a header and is skipped on import.
+//! - An empty name is a color segment without a name.
+//! - The RGB of transparent colors is kept in hex.
+
+use super::color_segments::color_segment::{
+    color::{Color, ParseHexColorError},
+    color_name::{ColorName, ColorNameError},
+    ColorSegment,
+};
+use super::Colorset;
+use std::{error, fmt, io};
+
+const CSV_HEADER: &str = "name,hex,transparent";
+
+/// Quote `field` if needed.
+fn quote_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Split a row into fields. Returns None if a quote is not closed.
+fn split_row(row: &str) -> Option<Vec<String>> {
+    let mut fields = vec![String::new()];
+    let mut chars = row.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut()?;
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => fields.push(String::new()),
+            (c, _) => field.push(c),
+        }
+    }
+
+    (!quoted).then_some(fields)
+}
+
+impl Colorset {
+    /// Export to CSV text. Lines end with LF.
+    pub fn to_csv(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writeln!(writer, "{}", CSV_HEADER)?;
+
+        for cs in self.color_segments.iter() {
+            let color = cs.get_color_ref();
+            writeln!(
+                writer,
+                "{},{},{}",
+                quote_field(cs.get_color_name_ref().map_or("", |name| name)),
+                color.get_hex_color(true),
+                color.get_transparency()
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Import from CSV text.
+    ///
+    /// Both CRLF and LF line endings are accepted, and empty lines are skipped.
+    /// Line breaks in quoted fields are kept as they are.
+    /// The colorset is named "NewColorset".
+    pub fn from_csv(mut reader: impl io::BufRead) -> Result<Colorset, CsvError> {
+        let mut colorset = Colorset::with_capacity("NewColorset", 0).unwrap();
+
+        let mut header_skipped = false;
+        let mut line_count = 0;
+        let mut row = String::new();
+        loop {
+            row.clear();
+            let line_number = line_count + 1;
+            // read lines until the quotes of the row are closed
+            let fields = loop {
+                if reader.read_line(&mut row)? == 0 {
+                    if row.is_empty() {
+                        break None;
+                    }
+                    return Err(CsvError::InvalidRow(line_number));
+                }
+                line_count += 1;
+                if let Some(fields) = split_row(row.trim_end_matches(['\r', '\n'])) {
+                    break Some(fields);
+                }
+            };
+            let Some(fields) = fields else {
+                break;
+            };
+            if row.trim_end_matches(['\r', '\n']).is_empty() {
+                continue;
+            }
+            if !std::mem::replace(&mut header_skipped, true) {
+                continue;
+            }
+
+            let [name, hex, transparent] = fields.as_slice() else {
+                return Err(CsvError::InvalidRow(line_number));
+            };
+
+            let transparency = match transparent.trim() {
+                "true" => true,
+                "false" => false,
+                _ => return Err(CsvError::InvalidTransparent(line_number)),
+            };
+            let color = Color::new_with_hex_color(hex.trim(), transparency)
+                .map_err(|err| CsvError::InvalidHex(line_number, err))?;
+            let color_name = if name.is_empty() {
+                None
+            } else {
+                Some(
+                    ColorName::with_str(name)
+                        .map_err(|err| CsvError::ColorName(line_number, err))?,
+                )
+            };
+            colorset
+                .color_segments
+                .push(ColorSegment::new(color, color_name));
+        }
+
+        if colorset.color_segments.is_empty() {
+            return Err(CsvError::EmptySegments);
+        }
+
+        Ok(colorset)
+    }
+}
+
+/// CsvError
+///
+/// Errors of rows carry the line number.
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    /// The row is not 3 fields, or has an unclosed quote.
+    InvalidRow(usize),
+    InvalidHex(usize, ParseHexColorError),
+    /// The transparent field is neither `true` nor `false`.
+    InvalidTransparent(usize),
+    ColorName(usize, ColorNameError),
+    EmptySegments,
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> Self {
+        CsvError::Io(err)
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CsvError::*;
+        match self {
+            Io(err) => write!(f, "{}", err),
+            InvalidRow(line) => write!(f, "Line {} is not \"{}\".", line, CSV_HEADER),
+            InvalidHex(line, err) => write!(f, "Line {}: {}", line, err),
+            InvalidTransparent(line) => {
+                write!(f, "Line {}: transparent must be true or false.", line)
+            }
+            ColorName(line, err) => write!(f, "Line {}: {}", line, err),
+            EmptySegments => write!(f, "Color segments is empty!"),
+        }
+    }
+}
+
+impl error::Error for CsvError {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::ColorSegment;
+    use super::{Colorset, CsvError};
+
+    #[test]
+    fn csv_roundtrip_test() {
+        let mut colorset = Colorset::with_capacity("NewColorset", 4).unwrap();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(1, 2, 3, true, Some("Hidden")).unwrap());
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(255, 128, 0, false, Some("Orange, \"Deep\"")).unwrap());
+        colorset.push_hex("#0A0B0C").unwrap();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(0, 0, 255, false, Some("Two\r\nLines\n")).unwrap());
+
+        let mut csv = Vec::new();
+        colorset.to_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.clone()).unwrap(),
+            concat!(
+                "name,hex,transparent\n",
+                "Hidden,#010203,true\n",
+                "\"Orange, \"\"Deep\"\"\",#FF8000,false\n",
+                ",#0A0B0C,false\n",
+                "\"Two\r\nLines\n\",#0000FF,false\n"
+            )
+        );
+
+        let de_colorset = Colorset::from_csv(csv.as_slice()).unwrap();
+        assert_eq!(de_colorset, colorset);
+        assert!(de_colorset.color_segments[2].get_color_name_ref().is_none());
+    }
+
+    #[test]
+    fn csv_invalid_test() {
+        let import =
+            |rows: &str| Colorset::from_csv(format!("{}\n{}", super::CSV_HEADER, rows).as_bytes());
+
+        assert!(matches!(import("a,#000000"), Err(CsvError::InvalidRow(2))));
+        assert!(matches!(
+            import("\"a,#000000,false"),
+            Err(CsvError::InvalidRow(2))
+        ));
+        assert!(matches!(
+            import("\r\na,#00000G,false"),
+            Err(CsvError::InvalidHex(3, _))
+        ));
+        assert!(matches!(
+            import("a,#000000,yes"),
+            Err(CsvError::InvalidTransparent(2))
+        ));
+        assert!(matches!(
+            import(&format!("{},#000000,false", "t".repeat(65))),
+            Err(CsvError::ColorName(2, _))
+        ));
+        assert!(matches!(import(""), Err(CsvError::EmptySegments)));
+    }
+}
@@ -0,0 +1,188 @@
+//! Diff
+//!
+//! Changes between two colorsets.
+//!
+//! # Note
+//! Color segments are matched by color in order, like a line diff.
+//!     - A matched color segment whose name differs is renamed.
+//!     - Unmatched color segments are removed from the old one and added to the new one.
+//!     - Colors are compared by [`Color::cls_eq`](super::color_segments::color_segment::color::Color::cls_eq),
+//!       so an edited color is a removal and an addition.
+
+use super::color_segments::color_segment::ColorSegment;
+use super::Colorset;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+/// A change from the old colorset to the new one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorsetChange {
+    /// `index` is in the new colorset.
+    Added {
+        index: usize,
+        color_segment: ColorSegment,
+    },
+    /// `index` is in the old colorset.
+    Removed {
+        index: usize,
+        color_segment: ColorSegment,
+    },
+    /// `index` is in the new colorset.
+    Renamed {
+        index: usize,
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+fn fmt_color(cs: &ColorSegment) -> String {
+    let color = cs.get_color_ref();
+    if color.get_transparency() {
+        "transparent".to_string()
+    } else {
+        color.get_hex_color(true).to_lowercase()
+    }
+}
+
+fn fmt_name(name: Option<&str>) -> String {
+    name.map_or("(none)".to_string(), |name| format!("\"{}\"", name))
+}
+
+impl fmt::Display for ColorsetChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ColorsetChange::*;
+        match self {
+            Added { color_segment, .. } | Removed { color_segment, .. } => {
+                let (sign, verb) = if matches!(self, Added { .. }) {
+                    ("+", "Added")
+                } else {
+                    ("-", "Removed")
+                };
+                write!(f, "{} {} {}", sign, verb, fmt_color(color_segment))?;
+                if let Some(name) = color_segment.get_color_name_ref() {
+                    write!(f, " \"{}\"", **name)?;
+                }
+                Ok(())
+            }
+            Renamed { index, old, new } => write!(
+                f,
+                "~ Renamed index {} {} -> {}",
+                index,
+                fmt_name(old.as_deref()),
+                fmt_name(new.as_deref())
+            ),
+        }
+    }
+}
+
+impl Colorset {
+    /// Returns the changes from self to `other`, in the order of color segments.
+    pub fn diff(&self, other: &Colorset) -> Vec<ColorsetChange> {
+        let (old, new) = (&self.color_segments, &other.color_segments);
+        let same_color = |i: usize, j: usize| old[i].get_color_ref().cls_eq(new[j].get_color_ref());
+
+        // lengths of the longest common subsequences of old[i..] and new[j..]
+        let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = if same_color(i, j) {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let mut changes = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && same_color(i, j) {
+                let old_name = old[i].get_color_name_ref().map(|cn| cn.to_string());
+                let new_name = new[j].get_color_name_ref().map(|cn| cn.to_string());
+                if old_name != new_name {
+                    changes.push(ColorsetChange::Renamed {
+                        index: j,
+                        old: old_name,
+                        new: new_name,
+                    });
+                }
+                i += 1;
+                j += 1;
+            } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+                changes.push(ColorsetChange::Added {
+                    index: j,
+                    color_segment: new[j].clone(),
+                });
+                j += 1;
+            } else {
+                changes.push(ColorsetChange::Removed {
+                    index: i,
+                    color_segment: old[i].clone(),
+                });
+                i += 1;
+            }
+        }
+
+        changes
+    }
+
+    /// Returns the [`Colorset::diff`] as text, one change per line.
+    ///
+    /// ```text
+    /// + Added #ff0000 "Red"
+    /// - Removed #00ff00
+    /// ~ Renamed index 2 "Old" -> "New"
+    /// ```
+    pub fn changelog(&self, other: &Colorset) -> String {
+        self.diff(other)
+            .iter()
+            .map(|change| format!("{}\n", change))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::ColorSegment;
+    use super::{Colorset, ColorsetChange};
+
+    #[test]
+    fn changelog_test() {
+        let mut old = Colorset::new();
+        old.color_segments
+            .push(ColorSegment::with_val(0, 255, 0, false, None).unwrap());
+        old.color_segments
+            .push(ColorSegment::with_val(0, 0, 255, false, Some("Old")).unwrap());
+
+        let mut new = Colorset::new();
+        new.color_segments
+            .push(ColorSegment::with_val(255, 0, 0, false, Some("Red")).unwrap());
+        new.color_segments
+            .push(ColorSegment::with_val(0, 0, 255, false, Some("New")).unwrap());
+
+        assert_eq!(
+            old.changelog(&new),
+            concat!(
+                "+ Added #ff0000 \"Red\"\n",
+                "- Removed #00ff00\n",
+                "~ Renamed index 2 \"Old\" -> \"New\"\n",
+            )
+        );
+        assert_eq!(
+            new.diff(&old)[2],
+            ColorsetChange::Renamed {
+                index: 2,
+                old: Some("New".to_string()),
+                new: Some("Old".to_string())
+            }
+        );
+
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(old.changelog(&old), "");
+    }
+}
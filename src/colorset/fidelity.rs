@@ -0,0 +1,252 @@
+//! Fidelity
+//!
+//! Raw values of a cls file that are normalized away by [`TryFromBytes`](super::common::TryFromBytes).
+//!
+//! # Note
+//! The following are not restored by [`Colorset::as_bytes`].
+//!     - The sjis block of the colorset name.(It is re-encoded from utf8.)
+//!     - The RGB of transparent colors.
+//!     - Color name flags other than 0 and 1.
+//!
+//! [`RawFidelity`] records them and [`Colorset::as_bytes_preserving`] writes them back,
+//! as long as the corresponding part of the colorset was not edited.
+//! Size headers are always recomputed.
+
+use super::color_segments::{self, color_segment};
+use super::{colorset_name, common, Colorset, CLS_HEADER};
+use crate::error::ClsError;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use bytes::{Bytes, BytesMut};
+use nom;
+use zerocopy::AsBytes;
+
+/// RawFidelity
+///
+/// Only the values that differ from the normalized serialization are recorded.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RawFidelity {
+    name: Option<RawColorsetName>,
+    segments: Vec<Option<RawColorSegment>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RawColorsetName {
+    utf8: String,
+    sjis: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RawColorSegment {
+    color: [u8; 4],
+    color_name_flag: u32,
+}
+
+impl RawFidelity {
+    /// Returns true if [`Colorset::as_bytes`] reproduces the parsed bytes.
+    pub fn is_lossless(&self) -> bool {
+        self.name.is_none() && self.segments.iter().all(Option::is_none)
+    }
+}
+
+impl Colorset {
+    /// Parse cls bytes, also recording the raw values that cannot be reconstructed from [`Colorset`].
+    ///
+    /// Use [`Colorset::as_bytes_preserving`] to serialize with them.
+    pub fn try_from_bytes_preserving(
+        input: &[u8],
+    ) -> nom::IResult<&[u8], (Colorset, RawFidelity), ClsError> {
+        use common::TryFromBytes;
+        use nom::{
+            bytes::complete::take,
+            number::complete::{le_u16, le_u32},
+        };
+
+        let mut fidelity = RawFidelity::default();
+
+        // check cls header
+        let (input, header) = take(6usize)(input).map_err(common::unexpected_eof("Colorset", 6))?;
+        if header != CLS_HEADER {
+            let mut found = [0u8; 6];
+            found.copy_from_slice(header);
+            return Err(nom::Err::Failure(ClsError::InvalidMagicHeader(found)));
+        }
+
+        // colorset name
+        let (name_input, _) = le_u32(input)?;
+        let (name_input, sjis_bytes_size) = le_u16(name_input)?;
+        let (_, sjis_bytes) = take(sjis_bytes_size as usize)(name_input)?;
+        let (input, name) = colorset_name::ColorsetName::try_from_bytes(input)?;
+        if sjis_bytes != name.encode_sjis().as_slice() {
+            fidelity.name = Some(RawColorsetName {
+                utf8: name.to_string(),
+                sjis: sjis_bytes.to_vec(),
+            });
+        }
+
+        // unknown number
+        let (input, unknown_number) = le_u32(input)?;
+
+        // color segments
+        let (rest, color_segments) = color_segments::ColorSegments::try_from_bytes(input)?;
+        let (mut segment_input, _) = take(8usize)(input)?;
+        for _ in 0..color_segments.len() {
+            let (raw, _) = le_u32(segment_input)?;
+            let (raw, raw_color) = take(4usize)(raw)?;
+            let (_, color_name_flag) = le_u32(raw)?;
+
+            let (next, _) = color_segment::ColorSegment::try_from_bytes(segment_input)?;
+            segment_input = next;
+
+            let (_, color) = color_segment::color::Color::try_from_bytes(raw_color)?;
+            let mut normalized_color = BytesMut::with_capacity(4);
+            common::ExtendBytesMut::extend_bytes(&color, &mut normalized_color);
+
+            if raw_color != normalized_color.as_ref() || color_name_flag > 1 {
+                let mut color = [0u8; 4];
+                color.copy_from_slice(raw_color);
+                fidelity.segments.push(Some(RawColorSegment {
+                    color,
+                    color_name_flag,
+                }));
+            } else {
+                fidelity.segments.push(None);
+            }
+        }
+
+        let colorset = Colorset {
+            name,
+            color_segments,
+            dirty: false,
+            metadata: super::sidecar::ColorsetMetadata::default(),
+            unknown_number,
+        };
+        Ok((rest, (colorset, fidelity)))
+    }
+
+    /// Serialize to cls bytes, writing back the raw values recorded in `fidelity`.
+    ///
+    /// # Note
+    /// A raw value is only used while the part it belongs to is unchanged,
+    /// e.g. a hidden RGB is dropped once the color at that index is edited.
+    pub fn as_bytes_preserving(&self, fidelity: &RawFidelity) -> Bytes {
+        use common::{ClsSize, ExtendBytesMut};
+        let mut extended = BytesMut::with_capacity(self.size_in_cls() as usize);
+
+        // extend cls header
+        extended.extend_from_slice(&CLS_HEADER);
+
+        // extend colorset name
+        match fidelity.name.as_ref() {
+            Some(raw_name) if raw_name.utf8 == **self.name => {
+                let utf8_buf = raw_name.utf8.as_bytes();
+                let bytesize_header = 8 + raw_name.sjis.len() as u32 + utf8_buf.len() as u32;
+
+                extended.extend_from_slice(bytesize_header.as_bytes());
+                extended.extend_from_slice((raw_name.sjis.len() as u16).as_bytes());
+                extended.extend_from_slice(&raw_name.sjis);
+                extended.extend_from_slice(self.name.raw_delimiter().as_bytes());
+                extended.extend_from_slice((utf8_buf.len() as u16).as_bytes());
+                extended.extend_from_slice(utf8_buf);
+            }
+            _ => self.name.extend_bytes(&mut extended),
+        }
+
+        // extend unknown number
+        extended.extend_from_slice(self.unknown_number.as_bytes());
+
+        // extend color segments
+        extended.extend_from_slice((self.color_segments.len() as u32).as_bytes());
+        extended.extend_from_slice(self.color_segments.size_contents_in_cls().as_bytes());
+        for (idx, cs) in self.color_segments.iter().enumerate() {
+            let mut segment_bytes = BytesMut::with_capacity(cs.size_in_cls() as usize);
+            cs.extend_bytes(&mut segment_bytes);
+
+            if let Some(Some(raw)) = fidelity.segments.get(idx) {
+                use color_segment::color::Color;
+                use common::TryFromBytes;
+
+                let unchanged_color = matches!(
+                    (Color::try_from_bytes(&raw.color), Color::try_from_bytes(&segment_bytes[4..8])),
+                    (Ok((_, raw_color)), Ok((_, color))) if raw_color == color
+                );
+                if unchanged_color {
+                    segment_bytes[4..8].copy_from_slice(&raw.color);
+                }
+                // Only a missing color name can carry an unusual flag.
+                if raw.color_name_flag > 1 && segment_bytes[8..12] == [0, 0, 0, 0] {
+                    segment_bytes[8..12].copy_from_slice(raw.color_name_flag.as_bytes());
+                }
+            }
+
+            extended.extend_from_slice(&segment_bytes);
+        }
+
+        extended.freeze()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::ColorSegment;
+    use super::Colorset;
+
+    fn hidden_rgb_setup() -> Vec<u8> {
+        let mut colorset = Colorset::new();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(0x12, 0x34, 0x56, false, Some("Hidden")).unwrap());
+        let mut cls_bytes = colorset.as_bytes().to_vec();
+
+        // Turn the last color transparent while keeping its RGB.
+        let alpha_pos = cls_bytes.len() - (2 + 12) - 4 - 1;
+        assert_eq!(
+            cls_bytes[alpha_pos - 3..=alpha_pos],
+            [0x12, 0x34, 0x56, 0xFF]
+        );
+        cls_bytes[alpha_pos] = 0x00;
+
+        cls_bytes
+    }
+
+    #[test]
+    fn preserving_roundtrip_test() {
+        let cls_bytes = hidden_rgb_setup();
+
+        let (_, (colorset, fidelity)) = Colorset::try_from_bytes_preserving(&cls_bytes).unwrap();
+        assert!(!fidelity.is_lossless());
+        assert_ne!(colorset.as_bytes().as_ref(), cls_bytes.as_slice());
+        assert_eq!(
+            colorset.as_bytes_preserving(&fidelity).as_ref(),
+            cls_bytes.as_slice()
+        );
+    }
+
+    #[test]
+    fn preserving_edited_test() {
+        let cls_bytes = hidden_rgb_setup();
+
+        let (_, (mut colorset, fidelity)) =
+            Colorset::try_from_bytes_preserving(&cls_bytes).unwrap();
+        colorset.color_segments[1]
+            .get_color_mut_ref()
+            .set_transparency(false);
+
+        assert_eq!(colorset.as_bytes_preserving(&fidelity), colorset.as_bytes());
+    }
+
+    #[test]
+    fn preserving_invalid_header_test() {
+        use crate::error::ClsError;
+
+        let mut cls_bytes = hidden_rgb_setup();
+        cls_bytes[..6].copy_from_slice(b"\x89PNG\r\n");
+
+        assert!(matches!(
+            Colorset::try_from_bytes_preserving(&cls_bytes),
+            Err(nom::Err::Failure(ClsError::InvalidMagicHeader(found))) if &found == b"\x89PNG\r\n"
+        ));
+    }
+}
@@ -0,0 +1,260 @@
+//! GPL
+//!
+//! Text palette format of GIMP.
+//!
+//! ```text
+//! GIMP Palette
+//! Name: Sunset
+//! Columns: 4
+//! # comment
+//! 255 128   0 Orange
+//!   0   0   0 Untitled
+//! ```
+//!
+//! # Note
+//! - `Columns:` and comments are ignored.
+//! - Colors are opaque, and a row without a name gives a color segment without a name.
+//! - Too long names are truncated and reported as [`GplWarning`], instead of failing the import.
+//! - GPL has no transparency, so transparent colors are exported as `0 0 0` with ` (transparent)` after the name.
+
+use super::color_segments::color_segment::{color::Color, color_name::ColorName, ColorSegment};
+use super::Colorset;
+use std::{error, fmt, io};
+
+const GPL_HEADER: &str = "GIMP Palette";
+const TRANSPARENT_SUFFIX: &str = "(transparent)";
+
+/// Split the leading channel of a row.
+fn split_channel(row: &str) -> Option<(u8, &str)> {
+    let row = row.trim_start();
+    let end = row.find(char::is_whitespace).unwrap_or(row.len());
+    Some((row[..end].parse().ok()?, &row[end..]))
+}
+
+impl Colorset {
+    /// Export to GPL text. Lines end with LF.
+    pub fn to_gpl(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writeln!(writer, "{}", GPL_HEADER)?;
+        writeln!(writer, "Name: {}", *self.name)?;
+
+        for cs in self.color_segments.iter() {
+            let color = cs.get_color_ref();
+            let (red, green, blue) = if color.get_transparency() {
+                (0, 0, 0)
+            } else {
+                color.get_rgb()
+            };
+            write!(writer, "{:3} {:3} {:3}", red, green, blue)?;
+
+            match (cs.get_color_name_ref(), color.get_transparency()) {
+                (Some(name), false) => writeln!(writer, "\t{}", **name)?,
+                (Some(name), true) => writeln!(writer, "\t{} {}", **name, TRANSPARENT_SUFFIX)?,
+                (None, true) => writeln!(writer, "\t{}", TRANSPARENT_SUFFIX)?,
+                (None, false) => writeln!(writer)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Import from GPL text.
+    ///
+    /// The colorset is named by `Name:`, or "NewColorset" without it.
+    /// Returns the names truncated to fit in the cls file as warnings.
+    pub fn from_gpl(reader: impl io::BufRead) -> Result<(Colorset, Vec<GplWarning>), GplError> {
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(header) if header.trim() == GPL_HEADER => {}
+            _ => return Err(GplError::MissingHeader),
+        }
+
+        let mut colorset = Colorset::with_capacity("NewColorset", 0).unwrap();
+        let mut warnings = Vec::new();
+        for (idx, line) in lines.enumerate() {
+            let line = line?;
+            let line_number = idx + 2;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("Name:") {
+                let name = name.trim();
+                if colorset.name.set_str_truncated(name) {
+                    warnings.push(GplWarning::ColorsetNameTruncated {
+                        line: line_number,
+                        name: name.to_owned(),
+                    });
+                }
+                continue;
+            }
+
+            let (red, rest) = split_channel(line).ok_or(GplError::InvalidRow(line_number))?;
+            let (green, rest) = split_channel(rest).ok_or(GplError::InvalidRow(line_number))?;
+            let (blue, rest) = split_channel(rest).ok_or(GplError::InvalidRow(line_number))?;
+
+            let name = rest.trim();
+            let color_name = if name.is_empty() {
+                None
+            } else {
+                let (color_name, truncated) = ColorName::with_str_truncated(name);
+                if truncated {
+                    warnings.push(GplWarning::ColorNameTruncated {
+                        line: line_number,
+                        name: name.to_owned(),
+                    });
+                }
+                Some(color_name)
+            };
+            colorset.color_segments.push(ColorSegment::new(
+                Color::new(red, green, blue, false),
+                color_name,
+            ));
+        }
+
+        if colorset.color_segments.is_empty() {
+            return Err(GplError::EmptySegments);
+        }
+
+        Ok((colorset, warnings))
+    }
+}
+
+/// A name truncated by [`Colorset::from_gpl`], with its line number and the original name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GplWarning {
+    ColorsetNameTruncated { line: usize, name: String },
+    ColorNameTruncated { line: usize, name: String },
+}
+
+impl fmt::Display for GplWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use GplWarning::*;
+        match self {
+            ColorsetNameTruncated { line, name } => {
+                write!(f, "Line {}: colorset name \"{}\" is truncated.", line, name)
+            }
+            ColorNameTruncated { line, name } => {
+                write!(f, "Line {}: color name \"{}\" is truncated.", line, name)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GplError {
+    Io(io::Error),
+    MissingHeader,
+    /// The row at the line number is not "R G B name".
+    InvalidRow(usize),
+    EmptySegments,
+}
+
+impl From<io::Error> for GplError {
+    fn from(err: io::Error) -> Self {
+        GplError::Io(err)
+    }
+}
+
+impl fmt::Display for GplError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use GplError::*;
+        match self {
+            Io(err) => write!(f, "{}", err),
+            MissingHeader => write!(f, "\"{}\" header is missing.", GPL_HEADER),
+            InvalidRow(line) => write!(f, "Line {} is not \"R G B name\".", line),
+            EmptySegments => write!(f, "Color segments is empty!"),
+        }
+    }
+}
+
+impl error::Error for GplError {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::ColorSegment;
+    use super::{Colorset, GplError, GplWarning};
+
+    #[test]
+    fn from_gpl_test() {
+        let long_name = "t".repeat(70);
+        let gpl = format!(
+            "GIMP Palette\nName: Sunset\nColumns: 4\n# comment\n\n255 128   0\tDeep Orange\n  0 0 0\n1 2 3 {}\n",
+            long_name
+        );
+
+        let (colorset, warnings) = Colorset::from_gpl(gpl.as_bytes()).unwrap();
+        assert_eq!(*colorset.name, "Sunset");
+        assert_eq!(
+            colorset.iter_hex(true).collect::<Vec<_>>(),
+            vec!["#FF8000", "#000000", "#010203"]
+        );
+
+        let names = colorset
+            .color_segments
+            .iter()
+            .map(|cs| cs.get_color_name_ref().map(|name| name.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![Some("Deep Orange".to_string()), None, Some("t".repeat(64))]
+        );
+        assert_eq!(
+            warnings,
+            vec![GplWarning::ColorNameTruncated {
+                line: 8,
+                name: long_name
+            }]
+        );
+    }
+
+    #[test]
+    fn to_gpl_test() {
+        let mut colorset = Colorset::with_capacity("Sunset", 3).unwrap();
+        colorset.push_hex("#FF8000").unwrap();
+        colorset.push_hex("#0A0B0C").unwrap();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(1, 2, 3, true, Some("Hole")).unwrap());
+
+        let mut gpl = Vec::new();
+        colorset.to_gpl(&mut gpl).unwrap();
+        assert_eq!(
+            String::from_utf8(gpl.clone()).unwrap(),
+            "GIMP Palette\nName: Sunset\n255 128   0\n 10  11  12\n  0   0   0\tHole (transparent)\n"
+        );
+
+        // transparency is kept only in the name
+        let (de_colorset, warnings) = Colorset::from_gpl(gpl.as_slice()).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(*de_colorset.name, "Sunset");
+        assert!(de_colorset.color_segments[0].cls_eq(&colorset.color_segments[0]));
+        assert!(de_colorset.color_segments[1].cls_eq(&colorset.color_segments[1]));
+        assert_eq!(
+            de_colorset.color_segments[2]
+                .get_color_name_ref()
+                .map(|name| name.to_string()),
+            Some("Hole (transparent)".to_string())
+        );
+    }
+
+    #[test]
+    fn from_gpl_invalid_test() {
+        assert!(matches!(
+            Colorset::from_gpl("JASC-PAL\n0100\n".as_bytes()),
+            Err(GplError::MissingHeader)
+        ));
+        assert!(matches!(
+            Colorset::from_gpl("GIMP Palette\nName: Empty\n".as_bytes()),
+            Err(GplError::EmptySegments)
+        ));
+        assert!(matches!(
+            Colorset::from_gpl("GIMP Palette\n0 0 256 Over\n".as_bytes()),
+            Err(GplError::InvalidRow(2))
+        ));
+        assert!(matches!(
+            Colorset::from_gpl("GIMP Palette\n0 0\n".as_bytes()),
+            Err(GplError::InvalidRow(2))
+        ));
+    }
+}
@@ -0,0 +1,159 @@
+//! JASC-PAL
+//!
+//! Text palette format of Paint Shop Pro.
+//!
+//! ```text
+//! JASC-PAL
+//! 0100
+//! 2
+//! 255 128 0
+//! 0 0 0
+//! ```
+//!
+//! # Note
+//! JASC-PAL has neither transparency nor color names.
+//!     - Transparent colors are exported as `0 0 0`.
+//!     - Color names are dropped on export, and imported colors have no names.
+
+use super::color_segments::color_segment::ColorSegment;
+use super::Colorset;
+use crate::error::ClsError;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+const JASC_PAL_HEADER: &str = "JASC-PAL";
+const JASC_PAL_VERSION: &str = "0100";
+
+impl Colorset {
+    /// Export to JASC-PAL text. Lines end with CRLF.
+    pub fn to_jasc_pal(&self) -> String {
+        let mut jasc_pal = format!(
+            "{}\r\n{}\r\n{}\r\n",
+            JASC_PAL_HEADER,
+            JASC_PAL_VERSION,
+            self.color_segments.len()
+        );
+
+        for cs in self.color_segments.iter() {
+            let color = cs.get_color_ref();
+            let (red, green, blue) = if color.get_transparency() {
+                (0, 0, 0)
+            } else {
+                color.get_rgb()
+            };
+            jasc_pal.push_str(&format!("{} {} {}\r\n", red, green, blue));
+        }
+
+        jasc_pal
+    }
+
+    /// Import from JASC-PAL text.
+    ///
+    /// Both CRLF and LF line endings are accepted. The colorset is named "NewColorset".
+    pub fn from_jasc_pal(input: &str) -> Result<Colorset, ClsError> {
+        let invalid = |msg: String| ClsError::InvalidFormat(format!("JASC-PAL {}", msg));
+
+        let mut lines = input.lines().map(str::trim);
+
+        if lines.next() != Some(JASC_PAL_HEADER) {
+            return Err(invalid("header is missing".to_string()));
+        }
+        match lines.next() {
+            Some(JASC_PAL_VERSION) => {}
+            version => return Err(invalid(format!("version {:?} is unsupported", version))),
+        }
+        let count = lines
+            .next()
+            .and_then(|count| count.parse::<usize>().ok())
+            .ok_or_else(|| invalid("color count is invalid".to_string()))?;
+        if count == 0 {
+            return Err(ClsError::EmptySegments);
+        }
+
+        // the count is untrusted, so the capacity is bounded by the lines actually given
+        let lines = lines.filter(|line| !line.is_empty()).collect::<Vec<_>>();
+        let mut colorset = Colorset::with_capacity("NewColorset", count.min(lines.len()))?;
+        for (idx, line) in lines.into_iter().enumerate() {
+            let channels = line
+                .split_whitespace()
+                .map(str::parse::<u8>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| invalid(format!("color {}: {}", idx, err)))?;
+            match channels[..] {
+                [red, green, blue] => colorset
+                    .color_segments
+                    .push(ColorSegment::with_val(red, green, blue, false, None)?),
+                _ => return Err(invalid(format!("color {} is not \"R G B\"", idx))),
+            }
+        }
+
+        if colorset.color_segments.len() != count {
+            return Err(invalid(format!(
+                "color count is {}, but {} colors are found",
+                count,
+                colorset.color_segments.len()
+            )));
+        }
+
+        Ok(colorset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Colorset;
+    use crate::error::ClsError;
+
+    #[test]
+    fn jasc_pal_roundtrip_test() {
+        let mut colorset = Colorset::with_capacity("NewColorset", 3).unwrap();
+        for hex in ["#FF8000", "#000000", "#0A0B0C"] {
+            colorset.push_hex(hex).unwrap();
+        }
+
+        let jasc_pal = colorset.to_jasc_pal();
+        assert_eq!(
+            jasc_pal,
+            "JASC-PAL\r\n0100\r\n3\r\n255 128 0\r\n0 0 0\r\n10 11 12\r\n"
+        );
+
+        let de_colorset = Colorset::from_jasc_pal(&jasc_pal).unwrap();
+        assert_eq!(de_colorset, colorset);
+
+        // transparent is exported as black, and names are dropped
+        let new_colorset = Colorset::new();
+        let de_new_colorset = Colorset::from_jasc_pal(&new_colorset.to_jasc_pal()).unwrap();
+        assert_eq!(
+            de_new_colorset.iter_hex(true).collect::<Vec<_>>(),
+            vec!["#000000"]
+        );
+        assert!(de_new_colorset.color_segments[0]
+            .get_color_name_ref()
+            .is_none());
+    }
+
+    #[test]
+    fn jasc_pal_invalid_test() {
+        for invalid in [
+            "GIMP Palette\n",
+            "JASC-PAL\n0200\n1\n0 0 0\n",
+            "JASC-PAL\n0100\n2\n0 0 0\n",
+            "JASC-PAL\n0100\n1\n0 0 256\n",
+            "JASC-PAL\n0100\n1\n0 0\n",
+            "JASC-PAL\n0100\n18446744073709551615\n0 0 0\n",
+            "JASC-PAL\n0100\n100000000000000\n0 0 0\n",
+        ] {
+            assert!(matches!(
+                Colorset::from_jasc_pal(invalid),
+                Err(ClsError::InvalidFormat(_))
+            ));
+        }
+        assert!(matches!(
+            Colorset::from_jasc_pal("JASC-PAL\n0100\n0\n"),
+            Err(ClsError::EmptySegments)
+        ));
+    }
+}
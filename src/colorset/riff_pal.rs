@@ -0,0 +1,181 @@
+//! RIFF PAL
+//!
+//! Binary palette format of Windows.
+//!
+//! | bytes | contents |
+//! | --- | --- |
+//! | 4 | "RIFF" |
+//! | 4 | u32 size of the rest of the file |
+//! | 4 | "PAL " |
+//! | 4 | "data" |
+//! | 4 | u32 size of data chunk |
+//! | 2 | u16 palette version(0x0300) |
+//! | 2 | u16 number of entries |
+//! | 4 * n | entries of [red, green, blue, flags] |
+//!
+//! # Note
+//! RIFF PAL has neither transparency nor color names.
+//!     - Transparent colors are exported as `0 0 0`. Flags are always 0.
+//!     - Color names are dropped on export, and imported colors have no names.
+
+use super::color_segments::color_segment::ColorSegment;
+use super::{common, Colorset};
+use crate::error::ClsError;
+use alloc::format;
+use bytes::{Bytes, BytesMut};
+use nom;
+use zerocopy::AsBytes;
+
+const PAL_VERSION: u16 = 0x0300;
+
+impl Colorset {
+    /// Export to RIFF PAL bytes.
+    ///
+    /// Fails with [`ClsError::InvalidArgument`] if the colorset has more than 65535 colors,
+    /// the max number of entries of RIFF PAL.
+    pub fn to_riff_pal(&self) -> Result<Bytes, ClsError> {
+        let num_entries = u16::try_from(self.color_segments.len()).map_err(|_| {
+            ClsError::InvalidArgument(format!(
+                "RIFF PAL has at most {} entries, but {}",
+                u16::MAX,
+                self.color_segments.len()
+            ))
+        })?;
+        let data_size = 4 + 4 * num_entries as u32;
+        let riff_size = 4 + 8 + data_size;
+
+        let mut riff_pal = BytesMut::with_capacity(8 + riff_size as usize);
+
+        // extend RIFF header
+        riff_pal.extend_from_slice(b"RIFF");
+        riff_pal.extend_from_slice(riff_size.as_bytes());
+        riff_pal.extend_from_slice(b"PAL ");
+
+        // extend data chunk header
+        riff_pal.extend_from_slice(b"data");
+        riff_pal.extend_from_slice(data_size.as_bytes());
+
+        // extend LOGPALETTE
+        riff_pal.extend_from_slice(PAL_VERSION.as_bytes());
+        riff_pal.extend_from_slice(num_entries.as_bytes());
+        for cs in self.color_segments.iter() {
+            let color = cs.get_color_ref();
+            let (red, green, blue) = if color.get_transparency() {
+                (0, 0, 0)
+            } else {
+                color.get_rgb()
+            };
+            riff_pal.extend_from_slice(&[red, green, blue, 0]);
+        }
+
+        Ok(riff_pal.freeze())
+    }
+
+    /// Import from RIFF PAL bytes.
+    ///
+    /// Chunks other than "data" are skipped. The colorset is named "NewColorset".
+    pub fn from_riff_pal(input: &[u8]) -> Result<Colorset, ClsError> {
+        use common::unexpected_eof;
+        use nom::bytes::complete::{tag, take};
+        use nom::number::complete::{le_u16, le_u32};
+
+        let invalid = |msg: &str| ClsError::InvalidFormat(format!("RIFF PAL {}", msg));
+
+        // RIFF header
+        let (input, _) = tag::<_, _, ClsError>(b"RIFF".as_ref())(input)
+            .map_err(|_| invalid("RIFF header is missing"))?;
+        let (input, _) = le_u32(input).map_err(unexpected_eof("RIFF PAL", 4))?;
+        let (mut input, _) = tag::<_, _, ClsError>(b"PAL ".as_ref())(input)
+            .map_err(|_| invalid("form type is not \"PAL \""))?;
+
+        // find data chunk
+        let data = loop {
+            let (rest, chunk_id) = take(4usize)(input).map_err(unexpected_eof("RIFF PAL", 4))?;
+            let (rest, chunk_size) = le_u32(rest).map_err(unexpected_eof("RIFF PAL", 4))?;
+            let (rest, chunk) = take(chunk_size as usize)(rest)
+                .map_err(unexpected_eof("RIFF PAL", chunk_size as usize))?;
+            if chunk_id == b"data" {
+                break chunk;
+            }
+            // chunks are aligned to 2 bytes
+            input = &rest[(chunk_size as usize % 2).min(rest.len())..];
+        };
+
+        // LOGPALETTE
+        let (data, _) = le_u16(data).map_err(unexpected_eof("RIFF PAL", 2))?;
+        let (mut data, num_entries) = le_u16(data).map_err(unexpected_eof("RIFF PAL", 2))?;
+        if num_entries == 0 {
+            return Err(ClsError::EmptySegments);
+        }
+
+        let mut colorset = Colorset::with_capacity("NewColorset", num_entries as usize)?;
+        for _ in 0..num_entries {
+            let (rest, entry) = take(4usize)(data).map_err(unexpected_eof("RIFF PAL", 4))?;
+            colorset.color_segments.push(ColorSegment::with_val(
+                entry[0], entry[1], entry[2], false, None,
+            )?);
+            data = rest;
+        }
+
+        Ok(colorset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Colorset;
+    use crate::error::ClsError;
+
+    #[test]
+    fn riff_pal_roundtrip_test() {
+        let mut colorset = Colorset::with_capacity("NewColorset", 2).unwrap();
+        for hex in ["#FF8000", "#0A0B0C"] {
+            colorset.push_hex(hex).unwrap();
+        }
+
+        let riff_pal = colorset.to_riff_pal().unwrap();
+        assert_eq!(
+            riff_pal.as_ref(),
+            [
+                b'R', b'I', b'F', b'F', 24, 0, 0, 0, b'P', b'A', b'L', b' ', // RIFF header
+                b'd', b'a', b't', b'a', 12, 0, 0, 0, // data chunk header
+                0x00, 0x03, 2, 0, // LOGPALETTE header
+                255, 128, 0, 0, 10, 11, 12, 0, // entries
+            ]
+        );
+
+        let de_colorset = Colorset::from_riff_pal(&riff_pal).unwrap();
+        assert_eq!(de_colorset, colorset);
+    }
+
+    #[test]
+    fn riff_pal_invalid_test() {
+        let riff_pal = Colorset::new().to_riff_pal().unwrap();
+
+        assert!(matches!(
+            Colorset::from_riff_pal(&riff_pal[..riff_pal.len() - 1]),
+            Err(ClsError::UnexpectedEof { .. })
+        ));
+        assert!(matches!(
+            Colorset::from_riff_pal(b"RIFX\x00\x00\x00\x00PAL "),
+            Err(ClsError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn riff_pal_too_many_entries_test() {
+        let mut colorset = Colorset::with_capacity("NewColorset", 1).unwrap();
+        colorset.push_hex("#000000").unwrap();
+        let segment = colorset.color_segments[0].clone();
+        colorset
+            .color_segments
+            .resize(u16::MAX as usize + 1, segment);
+
+        assert!(matches!(
+            colorset.to_riff_pal(),
+            Err(ClsError::InvalidArgument(_))
+        ));
+        colorset.color_segments.pop();
+        assert!(colorset.to_riff_pal().is_ok());
+    }
+}
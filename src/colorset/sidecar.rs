@@ -0,0 +1,160 @@
+//! Sidecar
+//!
+//! Metadata of a colorset that cannot be stored in a cls file.
+//!
+//! # Note
+//! [`Colorset::save_with_sidecar`] writes it next to the cls file as `<cls_path>.json`,
+//! and [`Colorset::load_with_sidecar`] merges it back.
+//! The cls file itself stays readable by ClipStudioPaint.
+//! Both need the `std` feature.
+
+#[cfg(feature = "std")]
+use super::color_segments::color_segment::color::SerializeMode;
+use super::Colorset;
+#[cfg(feature = "std")]
+use crate::error::ClsError;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// ColorsetMetadata
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ColorsetMetadata {
+    pub notes: Option<String>,
+    pub category: Option<String>,
+}
+
+#[cfg(feature = "std")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Sidecar {
+    #[serde(flatten)]
+    metadata: ColorsetMetadata,
+    /// Serialize mode of each color.
+    #[serde(default)]
+    serialize_modes: Vec<SerializeMode>,
+}
+
+/// Returns the path of the sidecar of `cls_path`.
+#[cfg(feature = "std")]
+fn sidecar_path(cls_path: &Path) -> PathBuf {
+    let mut path = OsString::from(cls_path.as_os_str());
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+impl Colorset {
+    pub fn metadata(&self) -> &ColorsetMetadata {
+        &self.metadata
+    }
+
+    /// # Note
+    /// Editing the metadata does not make the colorset dirty, since it is not a part of the cls bytes.
+    pub fn metadata_mut(&mut self) -> &mut ColorsetMetadata {
+        &mut self.metadata
+    }
+}
+
+#[cfg(feature = "std")]
+impl Colorset {
+    /// Write the cls file to `cls_path` and the metadata to its sidecar `<cls_path>.json`.
+    ///
+    /// # Note
+    /// This clears the dirty flag once the cls file is written.
+    pub fn save_with_sidecar(&mut self, cls_path: &Path) -> Result<(), ClsError> {
+        let sidecar = Sidecar {
+            metadata: self.metadata.clone(),
+            serialize_modes: self
+                .color_segments
+                .iter()
+                .map(|cs| cs.get_color_ref().get_serialize_mode().clone())
+                .collect(),
+        };
+        let json = serde_json::to_string_pretty(&sidecar)
+            .map_err(|err| ClsError::InvalidFormat(err.to_string()))?;
+
+        std::fs::write(cls_path, self.as_bytes()).map_err(|err| ClsError::Io {
+            path: cls_path.to_path_buf(),
+            err,
+        })?;
+        self.mark_clean();
+        let path = sidecar_path(cls_path);
+        std::fs::write(&path, json).map_err(|err| ClsError::Io { path, err })?;
+
+        Ok(())
+    }
+
+    /// Read the cls file at `cls_path` and merge the metadata of its sidecar `<cls_path>.json`.
+    ///
+    /// # Note
+    /// A missing sidecar leaves the metadata empty.
+    /// The serialize modes are only restored if the number of colors still matches the cls file.
+    pub fn load_with_sidecar(cls_path: &Path) -> Result<Colorset, ClsError> {
+        let mut colorset = Colorset::try_from_path(cls_path)?;
+
+        let path = sidecar_path(cls_path);
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(colorset),
+            Err(err) => return Err(ClsError::Io { path, err }),
+        };
+        let sidecar: Sidecar = serde_json::from_str(&json)
+            .map_err(|err| ClsError::InvalidFormat(format!("{}: {}", path.display(), err)))?;
+
+        if sidecar.serialize_modes.len() == colorset.color_segments.len() {
+            colorset
+                .color_segments
+                .iter_mut()
+                .zip(sidecar.serialize_modes)
+                .for_each(|(cs, serialize_mode)| {
+                    cs.get_color_mut_ref().set_serialize_mode(serialize_mode)
+                });
+        }
+        colorset.metadata = sidecar.metadata;
+
+        Ok(colorset)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::super::color_segments::color_segment::{color::SerializeMode, ColorSegment};
+    use super::{sidecar_path, Colorset};
+
+    #[test]
+    fn sidecar_roundtrip_test() {
+        let mut colorset = Colorset::new();
+        colorset
+            .color_segments_mut()
+            .push(ColorSegment::with_val(255, 0, 0, false, Some("Red")).unwrap());
+        colorset.color_segments[1]
+            .get_color_mut_ref()
+            .set_serialize_mode_hex();
+        assert!(colorset.is_dirty());
+        colorset.metadata_mut().notes = Some("Skin tones for chapter 3".to_string());
+        colorset.metadata_mut().category = Some("Character".to_string());
+
+        let cls_path = std::env::temp_dir().join("cls_rs_sidecar_test.cls");
+        colorset.save_with_sidecar(&cls_path).unwrap();
+        assert!(!colorset.is_dirty());
+        let loaded = Colorset::load_with_sidecar(&cls_path).unwrap();
+        assert_eq!(loaded, colorset);
+        assert_eq!(loaded.metadata(), colorset.metadata());
+        assert_eq!(
+            loaded.color_segments[1]
+                .get_color_ref()
+                .get_serialize_mode(),
+            &SerializeMode::Hex
+        );
+
+        // Without the sidecar only the cls file is read.
+        std::fs::remove_file(sidecar_path(&cls_path)).unwrap();
+        let loaded = Colorset::load_with_sidecar(&cls_path).unwrap();
+        assert!(loaded.cls_eq(&colorset));
+        assert_eq!(loaded.metadata().notes, None);
+        std::fs::remove_file(&cls_path).unwrap();
+    }
+}
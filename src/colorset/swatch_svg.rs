@@ -0,0 +1,138 @@
+//! Swatch SVG
+//!
+//! Preview of a colorset as an SVG image.
+//!
+//! # Note
+//! - Color segments are laid out in a row of `swatch_size` squares, one `<rect>` each.
+//! - Transparent colors are filled with a checkerboard pattern.
+//! - Color names are drawn as `<text>` at the bottom of the swatches, in black or white for legibility.
+
+use super::Colorset;
+use alloc::{format, string::String};
+use core::fmt::Write;
+
+const CHECKER_ID: &str = "transparent";
+
+/// Escape text for XML.
+fn escape_xml(val: &str) -> String {
+    let mut escaped = String::with_capacity(val.len());
+    for c in val.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Colorset {
+    /// Export a preview of color segments as SVG.
+    ///
+    /// # Note
+    /// The coordinates are computed in u64, so a large `swatch_size` does not overflow.
+    pub fn to_swatch_svg(&self, swatch_size: u32) -> String {
+        let width = swatch_size as u64 * self.color_segments.len() as u64;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+            width, swatch_size
+        );
+
+        let has_transparent = self
+            .color_segments
+            .iter()
+            .any(|cs| cs.get_color_ref().get_transparency());
+        if has_transparent {
+            let _ = write!(
+                svg,
+                concat!(
+                    "<defs><pattern id=\"{}\" width=\"8\" height=\"8\" patternUnits=\"userSpaceOnUse\">",
+                    "<path d=\"M0 0h8v8H0z\" fill=\"#ffffff\"/>",
+                    "<path d=\"M0 0h4v4H0zM4 4h4v4H4z\" fill=\"#cccccc\"/>",
+                    "</pattern></defs>\n"
+                ),
+                CHECKER_ID
+            );
+        }
+
+        let font_size = (swatch_size / 6).max(1);
+        for (idx, cs) in self.color_segments.iter().enumerate() {
+            let color = cs.get_color_ref();
+            let x = swatch_size as u64 * idx as u64;
+            let fill = if color.get_transparency() {
+                format!("url(#{})", CHECKER_ID)
+            } else {
+                color.get_hex_color(true).to_lowercase()
+            };
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"0\" width=\"{2}\" height=\"{2}\" fill=\"{}\"/>",
+                x, fill, swatch_size
+            );
+
+            if let Some(color_name) = cs.get_color_name_ref() {
+                let text_fill = if !color.get_transparency() && color.relative_luminance() < 0.5 {
+                    "#ffffff"
+                } else {
+                    "#000000"
+                };
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" fill=\"{}\">{}</text>",
+                    x + swatch_size as u64 / 2,
+                    swatch_size - font_size / 2,
+                    font_size,
+                    text_fill,
+                    escape_xml(color_name)
+                );
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::ColorSegment;
+    use super::Colorset;
+
+    #[test]
+    fn swatch_svg_test() {
+        let mut colorset = Colorset::new();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(255, 128, 0, false, Some("R&D")).unwrap());
+        colorset.push_hex("#0A0B0C").unwrap();
+
+        let svg = colorset.to_swatch_svg(48);
+        assert!(svg
+            .starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"144\" height=\"48\""));
+        assert_eq!(svg.matches("<rect").count(), 3);
+        assert!(
+            svg.contains("<rect x=\"48\" y=\"0\" width=\"48\" height=\"48\" fill=\"#ff8000\"/>")
+        );
+        assert!(svg.contains("fill=\"url(#transparent)\""));
+        assert_eq!(svg.matches("<text").count(), 2);
+        assert!(svg.contains(">R&amp;D</text>"));
+
+        let mut opaque = Colorset::with_capacity("Opaque", 1).unwrap();
+        opaque.push_hex("#FFFFFF").unwrap();
+        assert!(!opaque.to_swatch_svg(16).contains("<pattern"));
+    }
+
+    #[test]
+    fn swatch_svg_large_size_test() {
+        let mut colorset = Colorset::new();
+        colorset.push_hex("#0A0B0C").unwrap();
+
+        let swatch_size = u32::MAX / 2 + 1;
+        let svg = colorset.to_swatch_svg(swatch_size);
+        assert!(svg.contains(&format!("width=\"{}\"", swatch_size as u64 * 2)));
+        assert!(svg.contains(&format!("<rect x=\"{}\"", swatch_size)));
+    }
+}
@@ -0,0 +1,371 @@
+//! Web Object
+//!
+//! Canonical JSON shape of [`Colorset`] for the web frontend.
+//!
+//! ```json
+//! {
+//!   "name": "NewColorset",
+//!   "color_segments": [
+//!     {
+//!       "color": { "red": 0, "green": 0, "blue": 0, "transparency": true, "alpha": 0 },
+//!       "color_name": "Color0"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! # Note
+//! - `color` is always in the struct form, regardless of the [`SerializeMode`](super::color_segments::color_segment::color::SerializeMode).
+//! - `alpha` may be omitted, and then it is 0x00 for transparent colors and 0xFF for the others.
+//!   A transparent color ignores `alpha`.
+//! - `color_name` is `null` when the color segment has no name.
+//! - `color_segments` must not be empty.
+
+use super::color_segments::{
+    self,
+    color_segment::{
+        self,
+        color::Color,
+        color_name::{ColorName, ColorNameError},
+    },
+};
+use super::colorset_name::{self, ColorsetNameError};
+use super::Colorset;
+use serde;
+use std::{error, fmt};
+
+#[cfg(feature = "web")]
+use super::web_utils::cast_js_number;
+#[cfg(feature = "web")]
+use crate::wasm::*;
+#[cfg(feature = "web")]
+use js_sys::Number;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct WebColorset {
+    name: String,
+    color_segments: Vec<WebColorSegment>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct WebColorSegment {
+    color: WebColor,
+    color_name: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct WebColor {
+    red: u8,
+    green: u8,
+    blue: u8,
+    transparency: bool,
+    #[serde(default)]
+    alpha: Option<u8>,
+}
+
+/// Shape of a single color segment for patching the frontend in place.
+#[cfg(any(feature = "web", test))]
+#[derive(Debug, serde::Serialize)]
+struct WebColorSegmentPatch {
+    name: Option<String>,
+    hex: String,
+    transparency: bool,
+}
+
+#[cfg(any(feature = "web", test))]
+impl From<&color_segment::ColorSegment> for WebColorSegmentPatch {
+    fn from(cs: &color_segment::ColorSegment) -> Self {
+        let color = cs.get_color_ref();
+        WebColorSegmentPatch {
+            name: cs.get_color_name_ref().map(|cn| cn.to_string()),
+            hex: color.get_hex_color(true),
+            transparency: color.get_transparency(),
+        }
+    }
+}
+
+/// Shape of a single color segment for reading it back by index.
+#[cfg(any(feature = "web", test))]
+#[derive(Debug, serde::Serialize)]
+struct WebColorSegmentRgb {
+    name: Option<String>,
+    red: u8,
+    green: u8,
+    blue: u8,
+    transparency: bool,
+}
+
+#[cfg(any(feature = "web", test))]
+impl From<&color_segment::ColorSegment> for WebColorSegmentRgb {
+    fn from(cs: &color_segment::ColorSegment) -> Self {
+        let color = cs.get_color_ref();
+        let (red, green, blue) = color.get_rgb();
+        WebColorSegmentRgb {
+            name: cs.get_color_name_ref().map(|cn| cn.to_string()),
+            red,
+            green,
+            blue,
+            transparency: color.get_transparency(),
+        }
+    }
+}
+
+impl From<&Colorset> for WebColorset {
+    fn from(colorset: &Colorset) -> Self {
+        let color_segments = colorset
+            .color_segments
+            .iter()
+            .map(|cs| {
+                let color = cs.get_color_ref();
+                let (red, green, blue) = color.get_rgb();
+                WebColorSegment {
+                    color: WebColor {
+                        red,
+                        green,
+                        blue,
+                        transparency: color.get_transparency(),
+                        alpha: Some(color.get_alpha()),
+                    },
+                    color_name: cs.get_color_name_ref().map(|cn| cn.to_string()),
+                }
+            })
+            .collect();
+
+        WebColorset {
+            name: colorset.name.to_string(),
+            color_segments,
+        }
+    }
+}
+
+impl TryFrom<WebColorset> for Colorset {
+    type Error = WebObjectError;
+
+    fn try_from(web_colorset: WebColorset) -> Result<Self, Self::Error> {
+        if web_colorset.color_segments.is_empty() {
+            return Err(WebObjectError::EmptyColorSegments);
+        }
+
+        let mut name = colorset_name::ColorsetName::new();
+        name.set_str(&web_colorset.name)?;
+
+        let mut color_segments =
+            color_segments::ColorSegments::with_capacity(web_colorset.color_segments.len());
+        for web_cs in web_colorset.color_segments {
+            let WebColor {
+                red,
+                green,
+                blue,
+                transparency,
+                alpha,
+            } = web_cs.color;
+            let color = match alpha {
+                Some(alpha) if !transparency => Color::with_alpha(red, green, blue, alpha),
+                _ => Color::new(red, green, blue, transparency),
+            };
+            let color_name = web_cs
+                .color_name
+                .as_deref()
+                .map(ColorName::with_str)
+                .transpose()?;
+            color_segments.push(color_segment::ColorSegment::new(color, color_name));
+        }
+
+        Ok(Colorset {
+            name,
+            color_segments,
+            dirty: false,
+            metadata: super::sidecar::ColorsetMetadata::default(),
+            unknown_number: super::DEFAULT_UNKNOWN_NUMBER,
+        })
+    }
+}
+
+impl Colorset {
+    /// Serialize to the canonical JSON shape.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&WebColorset::from(self)).expect("WebColorset is serializable")
+    }
+
+    /// Deserialize from the canonical JSON shape.
+    pub fn from_json(json: &str) -> Result<Colorset, WebObjectError> {
+        let web_colorset: WebColorset = serde_json::from_str(json)?;
+        Colorset::try_from(web_colorset)
+    }
+}
+
+/// API for wasm
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Colorset {
+    /// Returns the canonical web object.
+    ///
+    /// Unlike `getJSObject`, the shape does not depend on the serialize mode of colors,
+    /// and can be passed back to `fromWebObject`.
+    #[wasm_bindgen(js_name = "toWebObject")]
+    pub fn to_web_object(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&WebColorset::from(self)).map_err(|err| err.into())
+    }
+
+    /// Returns `{ name, red, green, blue, transparency }` of the color segment at `idx`.
+    ///
+    /// `name` is `null` when the color segment has no name.
+    #[wasm_bindgen(js_name = "getColorSegment")]
+    pub fn get_color_segment(&self, idx: Number) -> Result<JsValue, JsValue> {
+        let idx: usize = cast_js_number(idx).ok_or_else(|| JsValue::from("Invalid Index"))?;
+        let cs = self.color_segments.get(idx).ok_or_else(|| {
+            JsValue::from(format!(
+                "{}th colorsegment does not exist (length: {})",
+                idx,
+                self.color_segments.len()
+            ))
+        })?;
+
+        serde_wasm_bindgen::to_value(&WebColorSegmentRgb::from(cs)).map_err(|err| err.into())
+    }
+
+    #[wasm_bindgen(js_name = "fromWebObject")]
+    pub fn from_web_object(js: JsValue) -> Result<Colorset, JsValue> {
+        let web_colorset: WebColorset = serde_wasm_bindgen::from_value(js)?;
+        Colorset::try_from(web_colorset).map_err(|err| JsValue::from(err.to_string()))
+    }
+}
+
+#[cfg(feature = "web")]
+impl color_segment::ColorSegment {
+    /// Returns `{ name, hex, transparency }` of this color segment.
+    ///
+    /// `name` is `null` when the color segment has no name, `hex` is "#RRGGBB".
+    pub fn to_web_object(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&WebColorSegmentPatch::from(self)).map_err(|err| err.into())
+    }
+}
+
+#[derive(Debug)]
+pub enum WebObjectError {
+    Json(serde_json::Error),
+    ColorsetName(ColorsetNameError),
+    ColorName(ColorNameError),
+    EmptyColorSegments,
+}
+
+impl From<serde_json::Error> for WebObjectError {
+    fn from(err: serde_json::Error) -> Self {
+        WebObjectError::Json(err)
+    }
+}
+
+impl From<ColorsetNameError> for WebObjectError {
+    fn from(err: ColorsetNameError) -> Self {
+        WebObjectError::ColorsetName(err)
+    }
+}
+
+impl From<ColorNameError> for WebObjectError {
+    fn from(err: ColorNameError) -> Self {
+        WebObjectError::ColorName(err)
+    }
+}
+
+impl fmt::Display for WebObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use WebObjectError::*;
+        match self {
+            Json(err) => write!(f, "{}", err),
+            ColorsetName(err) => write!(f, "{}", err),
+            ColorName(err) => write!(f, "{}", err),
+            EmptyColorSegments => write!(f, "Color segments is empty!"),
+        }
+    }
+}
+
+impl error::Error for WebObjectError {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color_segments::color_segment::{color::Color, ColorSegment};
+    use super::{Colorset, WebColorSegmentPatch, WebColorSegmentRgb, WebObjectError};
+
+    #[test]
+    fn json_roundtrip_test() {
+        let mut colorset = Colorset::new();
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(255, 128, 0, false, Some("Orange")).unwrap());
+        colorset
+            .color_segments
+            .push(ColorSegment::with_val(1, 2, 3, false, None).unwrap());
+        colorset
+            .color_segments
+            .push(ColorSegment::new(Color::with_alpha(1, 2, 3, 0x80), None));
+
+        let json = colorset.to_json();
+        assert_eq!(
+            json,
+            concat!(
+                "{\"name\":\"NewColorset\",\"color_segments\":[",
+                "{\"color\":{\"red\":0,\"green\":0,\"blue\":0,\"transparency\":true,\"alpha\":0},\"color_name\":\"Color0\"},",
+                "{\"color\":{\"red\":255,\"green\":128,\"blue\":0,\"transparency\":false,\"alpha\":255},\"color_name\":\"Orange\"},",
+                "{\"color\":{\"red\":1,\"green\":2,\"blue\":3,\"transparency\":false,\"alpha\":255},\"color_name\":null},",
+                "{\"color\":{\"red\":1,\"green\":2,\"blue\":3,\"transparency\":false,\"alpha\":128},\"color_name\":null}]}"
+            )
+        );
+
+        let de_colorset = Colorset::from_json(&json).unwrap();
+        assert_eq!(de_colorset, colorset);
+        assert!(de_colorset.cls_eq(&colorset));
+
+        // alpha defaults from transparency
+        let de_no_alpha = Colorset::from_json(concat!(
+            "{\"name\":\"NoAlpha\",\"color_segments\":[",
+            "{\"color\":{\"red\":1,\"green\":2,\"blue\":3,\"transparency\":false},\"color_name\":null}]}"
+        ))
+        .unwrap();
+        assert_eq!(
+            de_no_alpha.color_segments[0].get_color_ref().get_alpha(),
+            0xFF
+        );
+    }
+
+    #[test]
+    fn json_invalid_test() {
+        assert!(matches!(
+            Colorset::from_json("{\"name\":\"Empty\",\"color_segments\":[]}"),
+            Err(WebObjectError::EmptyColorSegments)
+        ));
+
+        let long_name = "a".repeat(65);
+        let json = format!(
+            "{{\"name\":\"{}\",\"color_segments\":[{{\"color\":{{\"red\":0,\"green\":0,\"blue\":0,\"transparency\":true}},\"color_name\":null}}]}}",
+            long_name
+        );
+        assert!(matches!(
+            Colorset::from_json(&json),
+            Err(WebObjectError::ColorsetName(_))
+        ));
+    }
+
+    #[test]
+    fn color_segment_patch_test() {
+        let cs = ColorSegment::with_val(255, 128, 0, false, Some("Orange")).unwrap();
+        assert_eq!(
+            serde_json::to_string(&WebColorSegmentPatch::from(&cs)).unwrap(),
+            "{\"name\":\"Orange\",\"hex\":\"#FF8000\",\"transparency\":false}"
+        );
+
+        let cs = ColorSegment::with_val(0, 0, 0, true, None).unwrap();
+        assert_eq!(
+            serde_json::to_string(&WebColorSegmentPatch::from(&cs)).unwrap(),
+            "{\"name\":null,\"hex\":\"#000000\",\"transparency\":true}"
+        );
+    }
+
+    #[test]
+    fn color_segment_rgb_test() {
+        let cs = ColorSegment::with_val(255, 128, 0, false, Some("Orange")).unwrap();
+        assert_eq!(
+            serde_json::to_string(&WebColorSegmentRgb::from(&cs)).unwrap(),
+            "{\"name\":\"Orange\",\"red\":255,\"green\":128,\"blue\":0,\"transparency\":false}"
+        );
+    }
+}
@@ -1,10 +1,14 @@
 //! Web Utils
 
+use super::color_segments::color_segment::color::{self, ParseHexColorError};
+use alloc::string::String;
+#[cfg(feature = "web")]
 use js_sys::Number;
+#[cfg(feature = "web")]
 use num_traits::{Num, NumCast};
-use std::num::ParseIntError;
 
 /// Cast JS Number to Primitive num type
+#[cfg(feature = "web")]
 pub fn cast_js_number<T: Num + NumCast>(js_number: Number) -> Option<T> {
     js_number
         .as_f64()
@@ -13,11 +17,25 @@ pub fn cast_js_number<T: Num + NumCast>(js_number: Number) -> Option<T> {
 }
 
 /// parse hex color string to rgb color
-/// expected string format is "#FFFFFF"
-pub fn parse_hex_color(hex_color: String) -> Result<(u8, u8, u8), ParseIntError> {
-    let red = u8::from_str_radix(&hex_color[1..3], 16)?;
-    let green = u8::from_str_radix(&hex_color[3..5], 16)?;
-    let blue = u8::from_str_radix(&hex_color[5..7], 16)?;
+/// expected string format is "#FFFFFF", "FFFFFF", "#FFF" or "FFF"
+pub fn parse_hex_color(hex_color: String) -> Result<(u8, u8, u8), ParseHexColorError> {
+    color::parse_hex_color(&hex_color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_hex_color;
 
-    Ok((red, green, blue))
+    #[test]
+    fn parse_hex_color_test() {
+        assert_eq!(
+            parse_hex_color("#FF8000".to_string()).unwrap(),
+            (255, 128, 0)
+        );
+        assert_eq!(parse_hex_color("F80".to_string()).unwrap(), (255, 136, 0));
+        // short input is an error instead of a panic
+        for invalid in ["", "#", "#FF80", "#FF800"] {
+            assert!(parse_hex_color(invalid.to_string()).is_err());
+        }
+    }
 }
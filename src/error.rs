@@ -0,0 +1,198 @@
+//! Error
+//!
+//! Crate level error for parsing cls files.
+
+use crate::colorset::color_segments::color_segment::color_name::ColorNameError;
+use crate::colorset::colorset_name::ColorsetNameError;
+use alloc::string::{FromUtf8Error, String};
+use core::{error, fmt};
+use nom::error::{ErrorKind, FromExternalError, ParseError};
+#[cfg(feature = "std")]
+use std::{io, path::PathBuf};
+
+/// ClsError
+///
+/// This is also the error type of the nom parsers in [`TryFromBytes`](crate::colorset::common::TryFromBytes).
+#[derive(Debug)]
+pub enum ClsError {
+    /// Input ended while reading `expected` bytes of `section`.
+    UnexpectedEof {
+        expected: usize,
+        section: &'static str,
+    },
+    /// The first 6 bytes are not the cls header.
+    InvalidMagicHeader([u8; 6]),
+    EmptySegments,
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+    SegmentCountMismatch {
+        expected: u32,
+        found: usize,
+    },
+    /// Bytes at `offset` from the start of the color segments are not a color segment,
+    /// while fewer color segments than declared are found.
+    MalformedSegment {
+        offset: usize,
+    },
+    /// Size header of `section` differs from its actual size.
+    SizeMismatch {
+        section: &'static str,
+        expected: u32,
+        found: usize,
+    },
+    /// Bytes are left after the colorset.
+    TrailingBytes(usize),
+    /// Color name flag other than 0 and 1.
+    InvalidNameFlag(u32),
+    ColorsetName(ColorsetNameError),
+    ColorName(ColorNameError),
+    InvalidUtf8(FromUtf8Error),
+    InvalidUtf16,
+    InvalidArgument(String),
+    /// Malformed input of other palette formats.
+    InvalidFormat(String),
+    /// Re-parsed colorset differs from the serialized one.
+    RoundtripMismatch(String),
+    /// Other nom errors.
+    Nom(ErrorKind),
+    /// IO error on a reader, see [`Colorset::from_reader`](crate::colorset::Colorset::from_reader).
+    #[cfg(feature = "std")]
+    Read(io::Error),
+    /// IO error on the file at `path`.
+    #[cfg(feature = "std")]
+    Io {
+        path: PathBuf,
+        err: io::Error,
+    },
+    /// Error in the file at `path`, in the section starting at `offset` bytes.
+    #[cfg(feature = "std")]
+    File {
+        path: PathBuf,
+        offset: usize,
+        err: Box<ClsError>,
+    },
+}
+
+impl<I> ParseError<I> for ClsError {
+    fn from_error_kind(_input: I, kind: ErrorKind) -> Self {
+        ClsError::Nom(kind)
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<I, E: Into<ClsError>> FromExternalError<I, E> for ClsError {
+    fn from_external_error(_input: I, _kind: ErrorKind, err: E) -> Self {
+        err.into()
+    }
+}
+
+impl From<nom::Err<ClsError>> for ClsError {
+    fn from(err: nom::Err<ClsError>) -> Self {
+        match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => err,
+            // Unreachable with complete parsers.
+            nom::Err::Incomplete(_) => ClsError::Nom(ErrorKind::Complete),
+        }
+    }
+}
+
+impl From<ColorsetNameError> for ClsError {
+    fn from(err: ColorsetNameError) -> Self {
+        ClsError::ColorsetName(err)
+    }
+}
+
+impl From<ColorNameError> for ClsError {
+    fn from(err: ColorNameError) -> Self {
+        ClsError::ColorName(err)
+    }
+}
+
+impl From<FromUtf8Error> for ClsError {
+    fn from(err: FromUtf8Error) -> Self {
+        ClsError::InvalidUtf8(err)
+    }
+}
+
+impl fmt::Display for ClsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ClsError::*;
+        match self {
+            UnexpectedEof { expected, section } => write!(
+                f,
+                "Unexpected end of input, {} bytes are expected in {}.",
+                expected, section
+            ),
+            InvalidMagicHeader(found) => write!(
+                f,
+                "Not a cls file, the header is {:02X?} instead of {:02X?}.",
+                found,
+                crate::colorset::CLS_HEADER
+            ),
+            EmptySegments => write!(f, "Color segments is empty!"),
+            IndexOutOfRange { index, len } => write!(
+                f,
+                "Index {} is out of range for {} color segments.",
+                index, len
+            ),
+            SegmentCountMismatch { expected, found } => write!(
+                f,
+                "Number of color segments is {}, but {} are found.",
+                expected, found
+            ),
+            MalformedSegment { offset } => {
+                write!(
+                    f,
+                    "Malformed color segment at byte {} of color segments.",
+                    offset
+                )
+            }
+            SizeMismatch {
+                section,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Size of {} is {} bytes, but {} bytes are found.",
+                section, expected, found
+            ),
+            TrailingBytes(len) => write!(f, "{} bytes are left after the colorset.", len),
+            InvalidNameFlag(flag) => write!(f, "Invalid color name flag {}.", flag),
+            ColorsetName(err) => write!(f, "{}", err),
+            ColorName(err) => write!(f, "{}", err),
+            InvalidUtf8(err) => write!(f, "{}", err),
+            InvalidUtf16 => write!(f, "Invalid utf16 string."),
+            InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+            InvalidFormat(msg) => write!(f, "Invalid format: {}", msg),
+            RoundtripMismatch(msg) => write!(f, "Roundtrip mismatch in {}.", msg),
+            Nom(kind) => write!(f, "Parse error: {}", kind.description()),
+            #[cfg(feature = "std")]
+            Read(err) => write!(f, "{}", err),
+            #[cfg(feature = "std")]
+            Io { path, err } => write!(f, "{}: {}", path.display(), err),
+            #[cfg(feature = "std")]
+            File { path, offset, err } => {
+                write!(f, "{} at byte {}: {}", path.display(), offset, err)
+            }
+        }
+    }
+}
+
+impl error::Error for ClsError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use ClsError::*;
+        match self {
+            InvalidUtf8(err) => Some(err),
+            #[cfg(feature = "std")]
+            Read(err) | Io { err, .. } => Some(err),
+            #[cfg(feature = "std")]
+            File { err, .. } => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -1,4 +1,9 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod colorset;
+pub mod error;
 pub mod utils;
 
 #[cfg(feature = "web")]
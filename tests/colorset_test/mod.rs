@@ -1,15 +1,21 @@
 use cls_rs::colorset::common::*;
 use cls_rs::colorset::Colorset;
 
-#[test]
-fn colorset_test_with_read_file() {
+fn read_testset() -> Vec<u8> {
     use std::env;
     use std::fs;
 
     let mut test_file_path = env::current_dir().unwrap();
     test_file_path.push("tests/colorset_test/testset.cls");
 
-    let test_file_bytes = fs::read(test_file_path).unwrap();
+    fs::read(test_file_path).unwrap()
+}
+
+// testset.cls has the sjis block of the name.
+#[cfg(feature = "sjis")]
+#[test]
+fn colorset_test_with_read_file() {
+    let test_file_bytes = read_testset();
 
     let (_, de_cls) = Colorset::try_from_bytes(&test_file_bytes).unwrap();
 
@@ -21,3 +27,13 @@ fn colorset_test_with_read_file() {
 
     assert_eq!(se_cls_vec, test_file_bytes);
 }
+
+#[test]
+fn colorset_test_roundtrip_read_file() {
+    let test_file_bytes = read_testset();
+
+    let (_, de_cls) = Colorset::try_from_bytes(&test_file_bytes).unwrap();
+    let (_, re_cls) = Colorset::try_from_bytes(&de_cls.as_bytes()).unwrap();
+
+    assert_eq!(re_cls, de_cls);
+}